@@ -1,4 +1,3 @@
-use std::env;
 use std::error::Error;
 
 use aliyun_openapi_core_rust_sdk::client::log_service::LogServiceClient;
@@ -15,11 +14,7 @@ struct ProjectList {
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     // create log service api client.
-    let aliyun_openapi_client = LogServiceClient::new(
-        env::var("ACCESS_KEY_ID")?,
-        env::var("ACCESS_KEY_SECRET")?,
-        "https://cn-hangzhou.log.aliyuncs.com",
-    );
+    let aliyun_openapi_client = LogServiceClient::from_env("https://cn-hangzhou.log.aliyuncs.com");
 
     // call `ListProject` api.
     let response = aliyun_openapi_client
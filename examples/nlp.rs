@@ -1,10 +1,8 @@
 use std::collections::HashMap;
-use std::env;
 use std::error::Error;
 
 use aliyun_openapi_core_rust_sdk::client::roa::ROAClient;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "PascalCase")]
@@ -24,11 +22,7 @@ struct Translate {
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     // create roa style api client.
-    let aliyun_openapi_client = ROAClient::new(
-        env::var("ACCESS_KEY_ID")?,
-        env::var("ACCESS_KEY_SECRET")?,
-        "http://mt.aliyuncs.com",
-    );
+    let aliyun_openapi_client = ROAClient::from_env("http://mt.aliyuncs.com");
 
     // create params.
     let mut params = HashMap::new();
@@ -42,8 +36,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let response = aliyun_openapi_client
         .version("2018-04-08")
         .post("/api/translate/web/general")
-        .header([("Content-Type".to_string(), "application/json".to_string())])?
-        .body(json!(params).to_string())?
+        .json_body(&params)?
         .json::<Translate>()
         .await?;
 
@@ -1,5 +1,5 @@
+use std::collections::HashMap;
 use std::error::Error;
-use std::{collections::HashMap, env};
 
 use aliyun_openapi_core_rust_sdk::client::rpc::RPClient;
 use serde::{Deserialize, Serialize};
@@ -36,11 +36,7 @@ struct DBInstances {
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     // create rpc style api client.
-    let aliyun_openapi_client = RPClient::new(
-        env::var("ACCESS_KEY_ID")?,
-        env::var("ACCESS_KEY_SECRET")?,
-        "https://rds.aliyuncs.com/",
-    );
+    let aliyun_openapi_client = RPClient::from_env("https://rds.aliyuncs.com/");
 
     // call `DescribeRegions` with empty queries and return `RegionList`
     let response = aliyun_openapi_client
@@ -1,6 +1,5 @@
 use aliyun_openapi_core_rust_sdk::client::roa::ROAClient;
 use serde::{Deserialize, Serialize};
-use std::env;
 use std::error::Error;
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -20,11 +19,7 @@ struct RegionList {
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     // create roa style api client.
-    let aliyun_openapi_client = ROAClient::new(
-        env::var("ACCESS_KEY_ID")?,
-        env::var("ACCESS_KEY_SECRET")?,
-        "https://ros.aliyuncs.com",
-    );
+    let aliyun_openapi_client = ROAClient::from_env("https://ros.aliyuncs.com");
 
     // call `DescribeRegions` with empty queries.
     let response = aliyun_openapi_client
@@ -1,5 +1,5 @@
+use std::collections::HashMap;
 use std::error::Error;
-use std::{collections::HashMap, env};
 
 use aliyun_openapi_core_rust_sdk::client::roa::ROAClient;
 use serde::{Deserialize, Serialize};
@@ -19,11 +19,7 @@ struct ClusterList {
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     // create roa style api client.
-    let aliyun_openapi_client = ROAClient::new(
-        env::var("ACCESS_KEY_ID")?,
-        env::var("ACCESS_KEY_SECRET")?,
-        "https://foas.cn-hangzhou.aliyuncs.com",
-    );
+    let aliyun_openapi_client = ROAClient::from_env("https://foas.cn-hangzhou.aliyuncs.com");
 
     // call `ListCluster` with empty queries.
     let response = aliyun_openapi_client
@@ -1,5 +1,4 @@
 use std::collections::HashMap;
-use std::env;
 use std::error::Error;
 
 use aliyun_openapi_core_rust_sdk::client::rpc::RPClient;
@@ -34,11 +33,7 @@ struct VpcList {
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     // create rpc style api client.
-    let aliyun_openapi_client = RPClient::new(
-        env::var("ACCESS_KEY_ID")?,
-        env::var("ACCESS_KEY_SECRET")?,
-        "https://vpc.aliyuncs.com/",
-    );
+    let aliyun_openapi_client = RPClient::from_env("https://vpc.aliyuncs.com/");
 
     // call `DescribeRegions` with empty queries, return `RegionList`
     let response = aliyun_openapi_client
@@ -0,0 +1,55 @@
+use std::{error::Error as StdError, fmt, pin::Pin, sync::Arc};
+
+use bytes::Bytes;
+use futures::{Stream, TryStream, TryStreamExt};
+
+/// The stream of request body.
+pub type BodyStream =
+    Pin<Box<dyn Stream<Item = Result<Bytes, Box<dyn StdError + Send + Sync>>> + Send + Sync>>;
+
+/// The body of request.
+#[derive(Clone)]
+pub enum Body {
+    /// The body held in memory.
+    Bytes(Bytes),
+    /// The factory to create the stream of body for every attempt of request.
+    Stream(Arc<dyn Fn() -> BodyStream + Send + Sync>),
+}
+
+impl Default for Body {
+    fn default() -> Self {
+        Body::Bytes(Bytes::new())
+    }
+}
+
+impl fmt::Debug for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Body::Bytes(bytes) => f.debug_tuple("Bytes").field(bytes).finish(),
+            Body::Stream(_) => f.write_str("Stream"),
+        }
+    }
+}
+
+impl Body {
+    /// Create a streaming body, `stream` is called for every attempt of request.
+    pub(crate) fn stream<F, S>(stream: F) -> Self
+    where
+        F: Fn() -> S + Send + Sync + 'static,
+        S: TryStream + Send + Sync + 'static,
+        S::Error: Into<Box<dyn StdError + Send + Sync>>,
+        Bytes: From<S::Ok>,
+    {
+        Body::Stream(Arc::new(move || {
+            Box::pin(stream().map_ok(Bytes::from).map_err(Into::into))
+        }))
+    }
+
+    /// The bytes of body, `None` for streaming body.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Body::Bytes(bytes) => Some(bytes),
+            Body::Stream(_) => None,
+        }
+    }
+}
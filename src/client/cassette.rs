@@ -0,0 +1,547 @@
+//! Record and replay api interactions with cassette files for offline tests.
+//!
+//! Enable the `test-util` feature to use it.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use quick_xml::{
+    events::{BytesText, Event},
+    Reader, Writer,
+};
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use url::form_urlencoded;
+
+use crate::client::{
+    error::{Error, Result},
+    transport::{request_url, HttpRequest, HttpResponse, HttpTransport, ReqwestTransport},
+};
+
+/// The placeholder of redacted values.
+const REDACTED: &str = "REDACTED";
+
+/// The params which change with every request or contain credentials.
+///
+/// The params whose names contain `Token` or `Secret` are redacted too.
+const VOLATILE_PARAMS: &[&str] = &[
+    "AccessKeyId",
+    "OIDCToken",
+    "SecurityToken",
+    "Signature",
+    "SignatureMethod",
+    "SignatureNonce",
+    "SignatureType",
+    "SignatureVersion",
+    "Timestamp",
+];
+
+/// The headers which change with every request or contain credentials.
+///
+/// The headers whose names contain `token` or `secret` are redacted too.
+const VOLATILE_HEADERS: &[&str] = &[
+    "authorization",
+    "date",
+    "x-acs-date",
+    "x-acs-security-token",
+    "x-acs-signature-method",
+    "x-acs-signature-nonce",
+    "x-log-signaturemethod",
+];
+
+/// The fields of JSON and XML bodies which contain credentials.
+const SECRET_FIELDS: &[&str] = &["AccessKeySecret", "SecurityToken"];
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Cassette {
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct RecordedRequest {
+    method: String,
+    host: String,
+    path: String,
+    /// The api name of RPC and ROA request.
+    action: Option<String>,
+    /// The queries with the volatile params redacted.
+    query: Vec<(String, String)>,
+    /// The params of form body with the volatile params redacted.
+    #[serde(default)]
+    form: Vec<(String, String)>,
+    /// The headers with the volatile headers redacted.
+    headers: BTreeMap<String, String>,
+    /// The body with the secret fields redacted, empty if it is recorded as form params.
+    body: Body,
+}
+
+impl RecordedRequest {
+    /// Record a request, redact the volatile params and headers, and the secrets of body.
+    fn new(request: &HttpRequest) -> Result<Self> {
+        let url = request_url(request.uri())?;
+        let query = record_params(url.query_pairs());
+        let body = request.body().as_bytes().unwrap_or_default();
+        let is_form = request
+            .headers()
+            .get("content-type")
+            .is_some_and(|v| v == "application/x-www-form-urlencoded");
+        let (form, body) = if is_form {
+            (record_params(form_urlencoded::parse(body)), Body::new(b""))
+        } else {
+            (Vec::new(), record_body(body))
+        };
+        let action = query
+            .iter()
+            .chain(form.iter())
+            .find(|(k, _)| k == "Action")
+            .map(|(_, v)| v.clone())
+            .or_else(|| {
+                request
+                    .headers()
+                    .get("x-acs-action")
+                    .and_then(|v| v.to_str().ok())
+                    .map(ToString::to_string)
+            });
+
+        Ok(RecordedRequest {
+            method: request.method().to_string(),
+            host: url.host_str().unwrap_or_default().to_string(),
+            path: url.path().to_string(),
+            action,
+            query,
+            form,
+            headers: record_headers(request.headers(), VOLATILE_HEADERS),
+            body,
+        })
+    }
+
+    /// Whether the recorded request matches by the action, uri and non-volatile params.
+    fn matches(&self, other: &RecordedRequest) -> bool {
+        self.method == other.method
+            && self.host == other.host
+            && self.path == other.path
+            && self.action == other.action
+            && self.query == other.query
+            && self.form == other.form
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct RecordedResponse {
+    status: u16,
+    headers: BTreeMap<String, String>,
+    body: Body,
+}
+
+/// The body of request or response, binary body is encoded with base64.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Body {
+    Text(String),
+    Base64(String),
+}
+
+impl Body {
+    fn new(body: &[u8]) -> Self {
+        match std::str::from_utf8(body) {
+            Ok(text) => Body::Text(text.to_string()),
+            Err(_) => Body::Base64(base64::encode(body)),
+        }
+    }
+
+    fn into_bytes(self) -> Result<Vec<u8>> {
+        match self {
+            Body::Text(text) => Ok(text.into_bytes()),
+            Body::Base64(data) => base64::decode(data)
+                .map_err(|e| Error::InvalidRequest(format!("Invalid cassette body: {e}"))),
+        }
+    }
+}
+
+/// Whether the name of param or header implies a credential, such as `OIDCToken`.
+fn is_secret(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.contains("token") || name.contains("secret")
+}
+
+/// Record the params sorted by name, redact the volatile params.
+fn record_params(params: form_urlencoded::Parse<'_>) -> Vec<(String, String)> {
+    let mut params: Vec<(String, String)> = params
+        .map(|(k, v)| {
+            let v = if VOLATILE_PARAMS.contains(&k.as_ref()) || is_secret(&k) {
+                REDACTED.to_string()
+            } else {
+                v.into_owned()
+            };
+            (k.into_owned(), v)
+        })
+        .collect();
+    params.sort();
+
+    params
+}
+
+/// Record the headers, redact the `redacted` headers and the secret headers.
+fn record_headers(headers: &HeaderMap, redacted: &[&str]) -> BTreeMap<String, String> {
+    headers
+        .iter()
+        .map(|(k, v)| {
+            let v = if redacted.contains(&k.as_str()) || is_secret(k.as_str()) {
+                REDACTED
+            } else {
+                v.to_str().unwrap_or_default()
+            };
+            (k.to_string(), v.to_string())
+        })
+        .collect()
+}
+
+/// Redact the secret fields of JSON value.
+fn redact_secrets(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map.iter_mut() {
+                if SECRET_FIELDS.contains(&k.as_str()) {
+                    *v = Value::String(REDACTED.to_string());
+                } else {
+                    redact_secrets(v);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
+/// Redact the text of secret elements of XML body.
+fn redact_xml_secrets(xml: &str) -> quick_xml::Result<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut writer = Writer::new(Vec::new());
+    let mut in_secret = false;
+    loop {
+        match reader.read_event()? {
+            Event::Eof => break,
+            Event::Start(e) => {
+                in_secret = SECRET_FIELDS
+                    .iter()
+                    .any(|field| e.local_name().as_ref() == field.as_bytes());
+                writer.write_event(Event::Start(e))?;
+            }
+            Event::End(e) => {
+                in_secret = false;
+                writer.write_event(Event::End(e))?;
+            }
+            Event::Text(_) | Event::CData(_) if in_secret => {
+                writer.write_event(Event::Text(BytesText::new(REDACTED)))?;
+            }
+            event => writer.write_event(event)?,
+        }
+    }
+
+    Ok(String::from_utf8_lossy(&writer.into_inner()).into_owned())
+}
+
+/// Record the body, redact the secret fields of JSON and XML body.
+///
+/// The XML body which can not be parsed is recorded as the placeholder, as its secrets can
+/// not be found.
+fn record_body(body: &[u8]) -> Body {
+    if let Ok(mut value) = serde_json::from_slice::<Value>(body) {
+        redact_secrets(&mut value);
+        return Body::Text(value.to_string());
+    }
+
+    match Body::new(body) {
+        Body::Text(text) if text.trim_start().starts_with('<') => {
+            Body::Text(redact_xml_secrets(&text).unwrap_or_else(|_| REDACTED.to_string()))
+        }
+        body => body,
+    }
+}
+
+/// The transport which sends requests with another transport, and records the
+/// interactions to a cassette file.
+///
+/// The signatures, nonces, dates and credentials of requests, and the credentials in
+/// JSON and XML bodies of requests and responses, are redacted. The XML body which can not
+/// be parsed is recorded as `REDACTED`, but the response is still returned as received. The
+/// cassette file is written after every interaction.
+#[derive(Debug)]
+pub struct RecordingTransport {
+    /// The transport to send requests.
+    transport: Arc<dyn HttpTransport>,
+    /// The path of cassette file.
+    path: PathBuf,
+    /// The recorded interactions, locked until the cassette file is written to keep the
+    /// writes in order.
+    cassette: futures::lock::Mutex<Cassette>,
+}
+
+impl RecordingTransport {
+    /// Create a transport to record the interactions to the cassette file `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        RecordingTransport {
+            transport: Arc::new(ReqwestTransport::default()),
+            path: path.into(),
+            cassette: Default::default(),
+        }
+    }
+
+    /// Set the transport to send requests.
+    ///
+    /// Default is [`ReqwestTransport`].
+    pub fn transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+
+        self
+    }
+}
+
+#[async_trait]
+impl HttpTransport for RecordingTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse> {
+        let recorded_request = RecordedRequest::new(&request)?;
+        let response = self.transport.send(request).await?;
+
+        let mut cassette = self.cassette.lock().await;
+        cassette.interactions.push(Interaction {
+            request: recorded_request,
+            response: RecordedResponse {
+                status: response.status().as_u16(),
+                headers: record_headers(response.headers(), &["set-cookie"]),
+                body: record_body(response.body()),
+            },
+        });
+        let content = serde_json::to_string_pretty(&*cassette)
+            .map_err(|e| Error::InvalidRequest(format!("Cannot serialize cassette: {e}")))?;
+        tokio::fs::write(&self.path, content).await.map_err(|e| {
+            Error::InvalidRequest(format!(
+                "Cannot write cassette {}: {e}",
+                self.path.display()
+            ))
+        })?;
+
+        Ok(response)
+    }
+}
+
+/// The transport which serves the interactions recorded in a cassette file.
+///
+/// A request is matched by the method, host, path, action and non-volatile params of the
+/// query and form body. The matched interactions are served in the recorded order, and each
+/// one is served once.
+#[derive(Debug)]
+pub struct ReplayTransport {
+    /// The recorded interactions, `None` if it has been served.
+    interactions: Mutex<Vec<Option<Interaction>>>,
+}
+
+impl ReplayTransport {
+    /// Create a transport to replay the cassette file `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let content = fs::read_to_string(&path).map_err(|e| {
+            Error::InvalidRequest(format!("Cannot read cassette {}: {e}", path.display()))
+        })?;
+        let cassette: Cassette = serde_json::from_str(&content).map_err(|e| {
+            Error::InvalidRequest(format!("Invalid cassette {}: {e}", path.display()))
+        })?;
+
+        Ok(ReplayTransport {
+            interactions: Mutex::new(cassette.interactions.into_iter().map(Some).collect()),
+        })
+    }
+}
+
+#[async_trait]
+impl HttpTransport for ReplayTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse> {
+        let recorded_request = RecordedRequest::new(&request)?;
+        let interaction = self
+            .interactions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter_mut()
+            .find(|interaction| {
+                interaction
+                    .as_ref()
+                    .is_some_and(|interaction| interaction.request.matches(&recorded_request))
+            })
+            .and_then(Option::take)
+            .ok_or_else(|| {
+                Error::InvalidRequest(format!(
+                    "No recorded interaction for {} {}",
+                    request.method(),
+                    request.uri()
+                ))
+            })?;
+
+        let response = interaction.response;
+        let mut builder = http::Response::builder().status(response.status);
+        for (k, v) in response.headers.iter() {
+            builder = builder.header(k, v);
+        }
+        builder
+            .body(response.body.into_bytes()?.into())
+            .map_err(|e| Error::InvalidRequest(format!("Invalid cassette response: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::client::{mock::MockTransport, roa::ROAClient, rpc::RPClient};
+
+    #[tokio::test]
+    async fn cassette_test() -> Result<()> {
+        let path = env::temp_dir().join(format!("cassette-{}.json", Uuid::new_v4()));
+
+        // record the interactions with mock transport.
+        let mock_transport = MockTransport::new()
+            .access_key("id", "secret")
+            .on_action(
+                "AssumeRole",
+                200,
+                r#"{"Credentials":{"AccessKeyId":"STS.id","AccessKeySecret":"sts-secret"}}"#,
+            )
+            .on_action("DescribeRegions", 200, r#"{"Regions":{}}"#)
+            .on_action(
+                "GetFederationToken",
+                200,
+                "<GetFederationTokenResponse><Credentials>\
+                 <AccessKeyId>STS.id</AccessKeyId>\
+                 <AccessKeySecret>xml-secret</AccessKeySecret>\
+                 <SecurityToken><![CDATA[xml-token]]></SecurityToken>\
+                 </Credentials></GetFederationTokenResponse>",
+            )
+            .on_action(
+                "GetInvalidXml",
+                200,
+                "<Credentials><AccessKeySecret>x</Credentials>",
+            )
+            .on_action(
+                "AssumeRoleWithOIDC",
+                200,
+                r#"{"Credentials":{"AccessKeyId":"STS.id","AccessKeySecret":"oidc-secret"}}"#,
+            )
+            .on_path("POST", "/json", 200, "{}")
+            .on_path("POST", "/xml", 200, "{}");
+        let recording_transport =
+            Arc::new(RecordingTransport::new(&path).transport(Arc::new(mock_transport)));
+        let roa_client = ROAClient::new("id", "secret", "https://cs.aliyuncs.com")
+            .transport(recording_transport.clone())
+            .version("2015-12-15");
+        let client = RPClient::new("id", "secret", "https://sts.aliyuncs.com/")
+            .transport(recording_transport)
+            .version("2015-04-01");
+        client.clone().post("AssumeRole").text().await?;
+        client.clone().get("DescribeRegions").text().await?;
+        client.clone().get("GetFederationToken").text().await?;
+        // the invalid XML body can not be redacted, it is returned but recorded as redacted.
+        let response = client.clone().get("GetInvalidXml").text().await?;
+        assert_eq!(response, "<Credentials><AccessKeySecret>x</Credentials>");
+        client
+            .clone()
+            .post("AssumeRoleWithOIDC")
+            .query([("OIDCToken", "oidc-token"), ("RoleArn", "role-arn")])
+            .text()
+            .await?;
+        // the secrets of JSON and XML request bodies are redacted.
+        let credentials = serde_json::json!({
+            "AccessKeyId": "STS.id",
+            "AccessKeySecret": "json-body-secret",
+        });
+        roa_client
+            .clone()
+            .post("/json")
+            .json_body(&credentials)?
+            .text()
+            .await?;
+        roa_client
+            .clone()
+            .post("/xml")
+            .body("<Credentials><SecurityToken>xml-body-token</SecurityToken></Credentials>")?
+            .add_header("content-type", "application/xml")?
+            .text()
+            .await?;
+
+        let content = fs::read_to_string(&path).unwrap();
+        for secret in [
+            "sts-secret",
+            "xml-secret",
+            "xml-token",
+            "oidc-token",
+            "oidc-secret",
+            "json-body-secret",
+            "xml-body-token",
+            "<AccessKeySecret>x",
+        ] {
+            assert!(!content.contains(secret));
+        }
+        let cassette: Cassette = serde_json::from_str(&content).unwrap();
+        assert_eq!(cassette.interactions.len(), 7);
+        assert!(matches!(
+            &cassette.interactions[3].response.body,
+            Body::Text(body) if body == REDACTED
+        ));
+        assert!(cassette.interactions[4]
+            .request
+            .form
+            .contains(&("OIDCToken".to_string(), REDACTED.to_string())));
+        assert!(cassette.interactions[4]
+            .request
+            .form
+            .contains(&("RoleArn".to_string(), "role-arn".to_string())));
+        for key in ["AccessKeyId", "Signature", "SignatureNonce", "Timestamp"] {
+            // the params of POST request are recorded from the form body.
+            assert!(cassette.interactions[0]
+                .request
+                .form
+                .contains(&(key.to_string(), REDACTED.to_string())));
+            assert!(cassette.interactions[1]
+                .request
+                .query
+                .contains(&(key.to_string(), REDACTED.to_string())));
+        }
+
+        // replay the interactions without credential.
+        let replay_transport = Arc::new(ReplayTransport::new(&path)?);
+        let client = RPClient::new("other-id", "other-secret", "https://sts.aliyuncs.com/")
+            .transport(replay_transport)
+            .version("2015-04-01");
+        let response = client.clone().get("DescribeRegions").text().await?;
+        assert_eq!(response, r#"{"Regions":{}}"#);
+        let response = client.clone().post("AssumeRole").text().await?;
+        assert!(response.contains(REDACTED));
+        let response = client.clone().get("GetFederationToken").text().await?;
+        assert!(response.contains("<AccessKeyId>STS.id</AccessKeyId>"));
+        assert!(response.contains(&format!("<AccessKeySecret>{REDACTED}</AccessKeySecret>")));
+        assert!(response.contains(&format!("<SecurityToken>{REDACTED}</SecurityToken>")));
+
+        // each interaction is served once.
+        assert!(matches!(
+            client.get("DescribeRegions").text().await,
+            Err(Error::InvalidRequest(_))
+        ));
+
+        fs::remove_file(&path).unwrap();
+
+        Ok(())
+    }
+}
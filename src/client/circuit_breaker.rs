@@ -0,0 +1,167 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+use crate::client::{
+    error::{Error, Result},
+    rate_limit::endpoint_host,
+};
+
+const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// The state of circuit of an endpoint host.
+#[derive(Debug)]
+enum State {
+    /// Requests are allowed, with the count of consecutive failures.
+    Closed(u32),
+    /// Requests fail fast until the cooldown ends.
+    Open(Instant),
+    /// A trial request is in flight since the instant.
+    HalfOpen(Instant),
+}
+
+/// A circuit breaker keyed by endpoint host.
+///
+/// The circuit of an endpoint opens after consecutive transport errors or 5xx responses,
+/// then requests to the endpoint fail fast with [`Error::CircuitOpen`]. After the cooldown
+/// the circuit is half-open, a trial request is allowed to close the circuit if it succeeds.
+///
+/// The states are shared by clones, so a circuit breaker can be attached to multiple clients.
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker {
+    /// The count of consecutive failures to open the circuit.
+    failure_threshold: u32,
+    /// The period to fail fast before a trial request.
+    cooldown: Duration,
+    /// The states of circuits by endpoint host.
+    states: Arc<Mutex<HashMap<String, State>>>,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        CircuitBreaker {
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            cooldown: DEFAULT_COOLDOWN,
+            states: Default::default(),
+        }
+    }
+}
+
+impl CircuitBreaker {
+    /// Create a circuit breaker which opens after 5 consecutive failures for 30 seconds.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the count of consecutive failures to open the circuit.
+    ///
+    /// Default is 5.
+    pub fn failure_threshold(mut self, failure_threshold: u32) -> Self {
+        self.failure_threshold = failure_threshold.max(1);
+
+        self
+    }
+
+    /// Set the period to fail fast before a trial request.
+    ///
+    /// Default is 30 seconds.
+    pub fn cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, State>> {
+        self.states.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Check whether a request to the endpoint is allowed.
+    pub(crate) fn acquire(&self, endpoint: &str) -> Result<()> {
+        let host = endpoint_host(endpoint);
+        let mut states = self.lock();
+        let state = states.entry(host.clone()).or_insert(State::Closed(0));
+
+        match *state {
+            State::Closed(_) => Ok(()),
+            // the trial request may be dropped without result, allow another one after cooldown.
+            State::Open(since) | State::HalfOpen(since) if since.elapsed() >= self.cooldown => {
+                *state = State::HalfOpen(Instant::now());
+                Ok(())
+            }
+            _ => Err(Error::CircuitOpen(host)),
+        }
+    }
+
+    /// Record the result of a request to the endpoint.
+    pub(crate) fn record(&self, endpoint: &str, success: bool) {
+        let host = endpoint_host(endpoint);
+        let mut states = self.lock();
+        let state = states.entry(host).or_insert(State::Closed(0));
+
+        *state = match (&*state, success) {
+            (_, true) => State::Closed(0),
+            (State::Closed(failures), false) if failures + 1 < self.failure_threshold => {
+                State::Closed(failures + 1)
+            }
+            // the request in flight when the circuit opened does not extend the cooldown.
+            (State::Open(since), false) => State::Open(*since),
+            (_, false) => State::Open(Instant::now()),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::advance;
+
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn circuit_breaker_test() {
+        let endpoint = "https://ecs.cn-hangzhou.aliyuncs.com/";
+        let circuit_breaker = CircuitBreaker::new()
+            .failure_threshold(2)
+            .cooldown(Duration::from_secs(10));
+
+        // open after consecutive failures.
+        assert!(circuit_breaker.acquire(endpoint).is_ok());
+        circuit_breaker.record(endpoint, false);
+        assert!(circuit_breaker.acquire(endpoint).is_ok());
+        circuit_breaker.record(endpoint, false);
+        assert!(matches!(
+            circuit_breaker.acquire(endpoint),
+            Err(Error::CircuitOpen(host)) if host == "ecs.cn-hangzhou.aliyuncs.com"
+        ));
+
+        // the failure of request in flight does not restart the cooldown.
+        advance(Duration::from_secs(5)).await;
+        circuit_breaker.record(endpoint, false);
+        advance(Duration::from_secs(5)).await;
+        assert!(circuit_breaker.acquire(endpoint).is_ok());
+        circuit_breaker.record(endpoint, false);
+
+        // other endpoints are not affected.
+        assert!(circuit_breaker
+            .acquire("https://ecs.cn-beijing.aliyuncs.com/")
+            .is_ok());
+
+        // allow only one trial request after cooldown, open again if it fails.
+        advance(Duration::from_secs(10)).await;
+        assert!(circuit_breaker.acquire(endpoint).is_ok());
+        assert!(circuit_breaker.acquire(endpoint).is_err());
+        circuit_breaker.record(endpoint, false);
+        assert!(circuit_breaker.acquire(endpoint).is_err());
+
+        // close if the trial request succeeds.
+        advance(Duration::from_secs(10)).await;
+        assert!(circuit_breaker.acquire(endpoint).is_ok());
+        circuit_breaker.record(endpoint, true);
+        assert!(circuit_breaker.acquire(endpoint).is_ok());
+        assert!(circuit_breaker.acquire(endpoint).is_ok());
+    }
+}
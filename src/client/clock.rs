@@ -0,0 +1,296 @@
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc,
+    },
+};
+
+use reqwest::header::HeaderValue;
+use time::{macros::format_description, Duration, OffsetDateTime, PrimitiveDateTime};
+use uuid::Uuid;
+
+use crate::client::error::Error;
+
+/// The source of current time to sign requests.
+pub trait Clock: Debug + Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> OffsetDateTime;
+}
+
+/// The clock of local system.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::now_utc()
+    }
+}
+
+/// The clock which always returns the same time, for reproducible signatures in tests.
+#[derive(Clone, Copy, Debug)]
+pub struct FixedClock(pub OffsetDateTime);
+
+impl Clock for FixedClock {
+    fn now(&self) -> OffsetDateTime {
+        self.0
+    }
+}
+
+/// The source of nonces to sign requests.
+pub trait NonceGenerator: Debug + Send + Sync {
+    /// Returns a new nonce.
+    fn nonce(&self) -> String;
+}
+
+/// The generator of random UUID v4 nonces.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UuidNonceGenerator;
+
+impl NonceGenerator for UuidNonceGenerator {
+    fn nonce(&self) -> String {
+        Uuid::new_v4().to_string()
+    }
+}
+
+/// The generator which always returns the same nonce, for reproducible signatures in tests.
+#[derive(Clone, Debug)]
+pub struct FixedNonceGenerator(pub String);
+
+impl NonceGenerator for FixedNonceGenerator {
+    fn nonce(&self) -> String {
+        self.0.clone()
+    }
+}
+
+/// The error codes which are caused by clock skew.
+///
+/// `SignatureDoesNotMatch` is not included, it is mostly caused by a wrong secret.
+const CLOCK_SKEW_ERROR_CODES: &[&str] = &["InvalidTimeStamp.Expired", "RequestTimeTooSkewed"];
+
+/// Whether the request failed by clock skew.
+pub(crate) fn is_clock_skew_error(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::InvalidResponse { error_code, .. } if CLOCK_SKEW_ERROR_CODES.contains(&error_code.as_str())
+    )
+}
+
+/// The min clock skew to correct, smaller skew is ignored.
+const MIN_CLOCK_SKEW: Duration = Duration::seconds(5);
+
+/// The offset between the local clock and the server clock, shared by clones of client.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ClockSkew {
+    /// The offset in seconds.
+    offset: Arc<AtomicI64>,
+}
+
+impl ClockSkew {
+    /// Returns the offset to add to local time.
+    pub(crate) fn offset(&self) -> Duration {
+        Duration::seconds(self.offset.load(Ordering::Relaxed))
+    }
+
+    /// Detect clock skew from the `Date` header of the failed response, and correct the offset.
+    pub(crate) fn detect(&self, clock: &dyn Clock, date: Option<&HeaderValue>, error: &Error) {
+        if !is_clock_skew_error(error) {
+            return;
+        }
+
+        // RFC 1123: %a, %d %b %Y %H:%M:%S GMT
+        let format = format_description!(
+            "[weekday repr:short], [day] [month repr:short] [year] [hour]:[minute]:[second] GMT"
+        );
+        let Some(server_time) = date
+            .and_then(|date| date.to_str().ok())
+            .and_then(|date| PrimitiveDateTime::parse(date, &format).ok())
+        else {
+            return;
+        };
+
+        let skew = server_time.assume_utc() - clock.now();
+        if (skew - self.offset()).abs() >= MIN_CLOCK_SKEW {
+            self.offset.store(skew.whole_seconds(), Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use async_trait::async_trait;
+    use time::{format_description::well_known::Iso8601, macros::datetime};
+    use tokio::sync::Notify;
+
+    use super::*;
+    use crate::client::{
+        error::{Error, Result},
+        mock::MockTransport,
+        rpc::RPClient,
+        signature::SignatureVersion,
+        transport::{HttpRequest, HttpResponse, HttpTransport},
+    };
+
+    /// The server whose clock is one hour ahead of the client.
+    ///
+    /// `DescribeRegions` fails if the timestamp is expired, `DescribeInstances` fails with
+    /// `InvalidParameter` after `released` is notified, and `DescribeZones` fails with
+    /// `SignatureDoesNotMatch`.
+    #[derive(Debug, Default)]
+    struct SkewedServerTransport {
+        timestamps: Mutex<Vec<(String, String)>>,
+        released: Notify,
+    }
+
+    #[async_trait]
+    impl HttpTransport for SkewedServerTransport {
+        async fn send(&self, request: HttpRequest) -> Result<HttpResponse> {
+            let server_time = datetime!(2023-10-26 11:22:32 UTC);
+            let query = request.uri().query().unwrap_or_default();
+            let param = |key: &str| {
+                url::form_urlencoded::parse(query.as_bytes())
+                    .find(|(k, _)| k == key)
+                    .map(|(_, v)| v.into_owned())
+                    .unwrap()
+            };
+            let (action, timestamp) = (param("Action"), param("Timestamp"));
+            self.timestamps
+                .lock()
+                .unwrap()
+                .push((action.clone(), timestamp.clone()));
+
+            let skew = server_time - OffsetDateTime::parse(&timestamp, &Iso8601::DEFAULT).unwrap();
+            let error_code = match action.as_str() {
+                "DescribeRegions" if skew.abs() > Duration::minutes(15) => {
+                    Some("InvalidTimeStamp.Expired")
+                }
+                "DescribeInstances" => {
+                    self.released.notified().await;
+                    Some("InvalidParameter")
+                }
+                "DescribeZones" => Some("SignatureDoesNotMatch"),
+                _ => None,
+            };
+            let response = match error_code {
+                Some(error_code) => http::Response::builder()
+                    .status(400)
+                    .header("date", "Thu, 26 Oct 2023 11:22:32 GMT")
+                    .body(
+                        format!(r#"{{"RequestId":"request-id","Code":"{error_code}","Message":"failed"}}"#)
+                            .into(),
+                    ),
+                None => http::Response::builder().status(200).body("{}".into()),
+            };
+
+            Ok(response.unwrap())
+        }
+    }
+
+    impl SkewedServerTransport {
+        /// The timestamps of the requests of `action`.
+        fn timestamps(&self, action: &str) -> Vec<String> {
+            self.timestamps
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(a, _)| a == action)
+                .map(|(_, timestamp)| timestamp.clone())
+                .collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn clock_skew_test() -> Result<()> {
+        let transport = Arc::new(SkewedServerTransport::default());
+        let client = RPClient::new("id", "secret", "https://ecs.aliyuncs.com/")
+            .transport(transport.clone())
+            .clock(Arc::new(FixedClock(datetime!(2023-10-26 10:22:32 UTC))))
+            .version("2014-05-26");
+
+        // retry once with corrected time.
+        client.clone().get("DescribeRegions").text().await?;
+        // the offset is kept by clones of client.
+        client.clone().get("DescribeRegions").text().await?;
+
+        let timestamps = transport.timestamps("DescribeRegions");
+        assert_eq!(timestamps.len(), 3);
+        assert!(timestamps[0].starts_with("2023-10-26T10:22:32"));
+        assert!(timestamps[1].starts_with("2023-10-26T11:22:32"));
+        assert!(timestamps[2].starts_with("2023-10-26T11:22:32"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn clock_skew_other_error_test() -> Result<()> {
+        let transport = Arc::new(SkewedServerTransport::default());
+        let client = RPClient::new("id", "secret", "https://ecs.aliyuncs.com/")
+            .transport(transport.clone())
+            .clock(Arc::new(FixedClock(datetime!(2023-10-26 10:22:32 UTC))))
+            .version("2014-05-26");
+
+        // the wrong secret does not shift the clock.
+        let result = client.clone().get("DescribeZones").text().await;
+        assert!(matches!(result, Err(Error::InvalidResponse { .. })));
+        assert_eq!(transport.timestamps("DescribeZones").len(), 1);
+
+        // the request failed for other reason is not retried, even if a concurrent request
+        // corrects the clock skew in the meantime.
+        let (result, _) = tokio::join!(client.clone().get("DescribeInstances").text(), async {
+            client.clone().get("DescribeRegions").text().await?;
+            transport.released.notify_one();
+            Ok::<_, Error>(())
+        });
+        assert!(matches!(
+            result,
+            Err(Error::InvalidResponse { error_code, .. }) if error_code == "InvalidParameter"
+        ));
+        assert_eq!(transport.timestamps("DescribeInstances").len(), 1);
+        assert_eq!(transport.timestamps("DescribeRegions").len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reproducible_signature_test() -> Result<()> {
+        let transport =
+            MockTransport::new()
+                .access_key("id", "secret")
+                .on_action("DescribeRegions", 200, "{}");
+        for signature_version in [SignatureVersion::V1, SignatureVersion::V3] {
+            for _ in 0..2 {
+                RPClient::new("id", "secret", "https://ecs.aliyuncs.com/")
+                    .transport(Arc::new(transport.clone()))
+                    .clock(Arc::new(FixedClock(datetime!(2023-10-26 10:22:32 UTC))))
+                    .nonce_generator(Arc::new(FixedNonceGenerator("nonce".to_string())))
+                    .signature_version(signature_version)
+                    .version("2014-05-26")
+                    .get("DescribeRegions")
+                    .text()
+                    .await?;
+            }
+        }
+
+        let requests: Vec<_> = transport
+            .requests()
+            .into_iter()
+            .map(|request| {
+                (
+                    request.url.to_string(),
+                    request.headers.get("authorization").cloned(),
+                )
+            })
+            .collect();
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[0], requests[1]);
+        assert!(requests[0].0.contains("SignatureNonce=nonce"));
+        assert!(requests[0].0.contains("Timestamp=2023-10-26T10%3A22%3A32"));
+        assert_eq!(requests[2], requests[3]);
+        assert!(requests[2].1.is_some());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,239 @@
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use time::OffsetDateTime;
+
+use crate::client::{
+    credential::{Credential, CredentialCache, CredentialProvider, StsCredential},
+    error::Result,
+    rpc::RPClient,
+    transport::{HttpTransport, ReqwestTransport},
+};
+
+/// Default endpoint of STS service.
+const DEFAULT_ENDPOINT: &str = "https://sts.aliyuncs.com/";
+const DEFAULT_ROLE_SESSION_NAME: &str = "aliyun-openapi-core-rust-sdk";
+const DEFAULT_DURATION_SECONDS: u64 = 3600;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AssumeRoleResponse {
+    credentials: StsCredential,
+}
+
+/// The provider that assumes a RAM role by calling STS `AssumeRole`.
+///
+/// The source credential is provided by another provider, so roles can be chained
+/// by using an `AssumeRoleProvider` as the source of another one.
+#[derive(Debug)]
+pub struct AssumeRoleProvider {
+    /// The provider of credentials to call STS.
+    source: Arc<dyn CredentialProvider>,
+    /// The api endpoint of STS service.
+    endpoint: String,
+    /// The ARN of the role to assume.
+    role_arn: String,
+    /// The session name of the assumed role.
+    role_session_name: String,
+    /// The policy to further restrict the assumed role.
+    policy: Option<String>,
+    /// The external id of the role.
+    external_id: Option<String>,
+    /// The valid period of the temporary credential.
+    duration_seconds: u64,
+    /// The transport to call STS.
+    transport: Arc<dyn HttpTransport>,
+    /// The client to call STS, built on the first refresh and shared by the later ones.
+    client: OnceLock<RPClient>,
+    /// The cached temporary credential.
+    cache: CredentialCache,
+}
+
+impl AssumeRoleProvider {
+    /// Create a provider to assume the role `role_arn` with the source credential.
+    pub fn new(source: Arc<dyn CredentialProvider>, role_arn: impl Into<String>) -> Self {
+        AssumeRoleProvider {
+            source,
+            endpoint: DEFAULT_ENDPOINT.to_string(),
+            role_arn: role_arn.into(),
+            role_session_name: DEFAULT_ROLE_SESSION_NAME.to_string(),
+            policy: None,
+            external_id: None,
+            duration_seconds: DEFAULT_DURATION_SECONDS,
+            transport: Arc::new(ReqwestTransport::default()),
+            client: OnceLock::new(),
+            cache: Default::default(),
+        }
+    }
+
+    /// Set the api endpoint of STS service.
+    ///
+    /// Default is `https://sts.aliyuncs.com/`.
+    pub fn endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+
+        self
+    }
+
+    /// Set the session name of the assumed role.
+    pub fn role_session_name(mut self, role_session_name: impl Into<String>) -> Self {
+        self.role_session_name = role_session_name.into();
+
+        self
+    }
+
+    /// Set the policy to further restrict the assumed role.
+    pub fn policy(mut self, policy: impl Into<String>) -> Self {
+        self.policy = Some(policy.into());
+
+        self
+    }
+
+    /// Set the external id of the role.
+    pub fn external_id(mut self, external_id: impl Into<String>) -> Self {
+        self.external_id = Some(external_id.into());
+
+        self
+    }
+
+    /// Set the valid period of the temporary credential in seconds.
+    ///
+    /// Default is 3600 seconds.
+    pub fn duration_seconds(mut self, duration_seconds: u64) -> Self {
+        self.duration_seconds = duration_seconds;
+
+        self
+    }
+
+    /// Set the transport to call STS.
+    ///
+    /// Default is [`ReqwestTransport`].
+    pub fn transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+
+        self
+    }
+
+    /// Call STS `AssumeRole` to get a new temporary credential.
+    async fn assume_role(&self) -> Result<(Credential, OffsetDateTime)> {
+        let mut queries = vec![
+            ("RoleArn".to_string(), self.role_arn.clone()),
+            (
+                "RoleSessionName".to_string(),
+                self.role_session_name.clone(),
+            ),
+            (
+                "DurationSeconds".to_string(),
+                self.duration_seconds.to_string(),
+            ),
+        ];
+        if let Some(policy) = self.policy.as_ref() {
+            queries.push(("Policy".to_string(), policy.clone()));
+        }
+        if let Some(external_id) = self.external_id.as_ref() {
+            queries.push(("ExternalId".to_string(), external_id.clone()));
+        }
+
+        let client = self.client.get_or_init(|| {
+            RPClient::from_provider(self.source.clone(), self.endpoint.as_str())
+                .transport(self.transport.clone())
+                .version("2015-04-01")
+        });
+        let response = client
+            .clone()
+            .post("AssumeRole")
+            .query(queries)
+            .json::<AssumeRoleResponse>()
+            .await?;
+
+        response.credentials.into_credential()
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for AssumeRoleProvider {
+    async fn get_credentials(&self) -> Result<Credential> {
+        self.cache.get_or_refresh(|| self.assume_role()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use time::{format_description::well_known::Rfc3339, Duration};
+    use url::form_urlencoded;
+
+    use super::*;
+    use crate::client::{credential::StaticCredentialProvider, mock::MockTransport};
+
+    fn mock_transport(expiration: OffsetDateTime) -> MockTransport {
+        let body = json!({
+            "RequestId": "request-id",
+            "Credentials": {
+                "AccessKeyId": "STS.id",
+                "AccessKeySecret": "sts-secret",
+                "SecurityToken": "sts-token",
+                "Expiration": expiration.format(&Rfc3339).unwrap(),
+            }
+        });
+
+        MockTransport::new().access_key("id", "secret").on_action(
+            "AssumeRole",
+            200,
+            body.to_string(),
+        )
+    }
+
+    #[tokio::test]
+    async fn assume_role_provider_cache_test() -> Result<()> {
+        let transport = mock_transport(OffsetDateTime::now_utc() + Duration::hours(1));
+        let provider = AssumeRoleProvider::new(
+            Arc::new(StaticCredentialProvider::new("id", "secret")),
+            "acs:ram::1:role/test",
+        )
+        .transport(Arc::new(transport.clone()));
+
+        for _ in 0..2 {
+            let credential = provider.get_credentials().await?;
+            assert_eq!(
+                credential,
+                Credential::new("STS.id", "sts-secret").with_security_token("sts-token")
+            );
+        }
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "POST");
+        let params: Vec<(String, String)> =
+            form_urlencoded::parse(requests[0].body.as_deref().unwrap_or_default())
+                .into_owned()
+                .collect();
+        for param in [
+            ("Action", "AssumeRole"),
+            ("AccessKeyId", "id"),
+            ("RoleArn", "acs:ram::1:role/test"),
+        ] {
+            assert!(params.contains(&(param.0.to_string(), param.1.to_string())));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn assume_role_provider_refresh_test() -> Result<()> {
+        let transport = mock_transport(OffsetDateTime::now_utc() + Duration::minutes(1));
+        let provider = AssumeRoleProvider::new(
+            Arc::new(StaticCredentialProvider::new("id", "secret")),
+            "acs:ram::1:role/test",
+        )
+        .transport(Arc::new(transport.clone()));
+
+        // the credential about to expire is refreshed with the same client.
+        provider.get_credentials().await?;
+        provider.get_credentials().await?;
+        assert_eq!(transport.requests().len(), 2);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,271 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use crate::client::{
+    credential::{
+        Credential, CredentialProvider, EcsRamRoleProvider, Env, EnvironmentProvider,
+        OidcRoleArnProvider, ProfileProvider,
+    },
+    error::{Error, Result},
+};
+
+const ECS_METADATA_ENV: &str = "ALIBABA_CLOUD_ECS_METADATA";
+const ECS_METADATA_DISABLED_ENV: &str = "ALIBABA_CLOUD_ECS_METADATA_DISABLED";
+
+/// The provider that tries the providers in order, and uses the first one which works.
+///
+/// The default chain is the same as the official SDKs:
+///
+/// 1. Env vars, see [`EnvironmentProvider`].
+/// 2. The config file of aliyun CLI, see [`ProfileProvider`].
+/// 3. The OIDC token of RRSA, see [`OidcRoleArnProvider::from_env`].
+/// 4. The RAM role of ECS instance, see [`EcsRamRoleProvider`]. The role name can be set by
+///    env var `ALIBABA_CLOUD_ECS_METADATA`, and it is skipped if env var
+///    `ALIBABA_CLOUD_ECS_METADATA_DISABLED` is `true`.
+///
+/// The provider which works is remembered, and the chain is tried again when it fails.
+#[derive(Debug)]
+pub struct DefaultCredentialChain {
+    /// The providers to try in order.
+    providers: Vec<Arc<dyn CredentialProvider>>,
+    /// The index of the provider which works.
+    selected: Mutex<Option<usize>>,
+}
+
+impl Default for DefaultCredentialChain {
+    fn default() -> Self {
+        Self::with_env(Env::default())
+    }
+}
+
+impl DefaultCredentialChain {
+    /// Create the default credential chain.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Create a credential chain with the providers.
+    pub fn with_providers(providers: Vec<Arc<dyn CredentialProvider>>) -> Self {
+        DefaultCredentialChain {
+            providers,
+            selected: Default::default(),
+        }
+    }
+
+    /// Create the default credential chain with the lookup of env vars.
+    pub(crate) fn with_env(env: Env) -> Self {
+        let mut providers: Vec<Arc<dyn CredentialProvider>> = vec![
+            Arc::new(EnvironmentProvider::new().env(env.clone())),
+            Arc::new(ProfileProvider::new().env(env.clone())),
+        ];
+        if let Ok(provider) = OidcRoleArnProvider::from_env_vars(&env) {
+            providers.push(Arc::new(provider));
+        }
+        if !env
+            .var(ECS_METADATA_DISABLED_ENV)
+            .is_some_and(|v| v.eq_ignore_ascii_case("true"))
+        {
+            let mut provider = EcsRamRoleProvider::new();
+            if let Some(role_name) = env.var(ECS_METADATA_ENV) {
+                provider = provider.role_name(role_name);
+            }
+            providers.push(Arc::new(provider));
+        }
+
+        Self::with_providers(providers)
+    }
+
+    /// Get the index of the provider which works.
+    fn selected(&self) -> Option<usize> {
+        *self.selected.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Remember the index of the provider which works.
+    fn select(&self, index: Option<usize>) {
+        *self.selected.lock().unwrap_or_else(|e| e.into_inner()) = index;
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for DefaultCredentialChain {
+    async fn get_credentials(&self) -> Result<Credential> {
+        let selected = self.selected();
+        let mut errors = Vec::new();
+        if let Some(index) = selected {
+            match self.providers[index].get_credentials().await {
+                Ok(credential) => return Ok(credential),
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+
+        for (index, provider) in self.providers.iter().enumerate() {
+            if Some(index) == selected {
+                continue;
+            }
+            match provider.get_credentials().await {
+                Ok(credential) => {
+                    self.select(Some(index));
+                    return Ok(credential);
+                }
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+        self.select(None);
+
+        Err(Error::InvalidCredential(format!(
+            "No credential found in the chain: [{}]",
+            errors.join(", ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env, fs,
+        sync::atomic::{AtomicBool, Ordering},
+    };
+
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::client::credential::StaticCredentialProvider;
+
+    #[derive(Debug)]
+    struct FailedProvider;
+
+    #[async_trait]
+    impl CredentialProvider for FailedProvider {
+        async fn get_credentials(&self) -> Result<Credential> {
+            Err(Error::InvalidCredential("failed".to_string()))
+        }
+    }
+
+    /// The provider which fails after `fail` is set.
+    #[derive(Debug, Default)]
+    struct ExpiredProvider {
+        fail: AtomicBool,
+    }
+
+    #[async_trait]
+    impl CredentialProvider for ExpiredProvider {
+        async fn get_credentials(&self) -> Result<Credential> {
+            if self.fail.load(Ordering::SeqCst) {
+                return Err(Error::InvalidCredential("expired".to_string()));
+            }
+
+            Ok(Credential::new("expired-id", "expired-secret"))
+        }
+    }
+
+    #[tokio::test]
+    async fn credential_chain_test() -> Result<()> {
+        let chain = DefaultCredentialChain::with_providers(vec![
+            Arc::new(FailedProvider),
+            Arc::new(StaticCredentialProvider::new("id", "secret")),
+        ]);
+        assert_eq!(
+            chain.get_credentials().await?,
+            Credential::new("id", "secret")
+        );
+        assert_eq!(chain.selected(), Some(1));
+
+        let chain = DefaultCredentialChain::with_providers(vec![Arc::new(FailedProvider)]);
+        assert!(matches!(
+            chain.get_credentials().await,
+            Err(Error::InvalidCredential(_))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn credential_chain_reprobe_test() -> Result<()> {
+        let expired = Arc::new(ExpiredProvider::default());
+        let chain = DefaultCredentialChain::with_providers(vec![
+            Arc::new(FailedProvider),
+            expired.clone(),
+            Arc::new(StaticCredentialProvider::new("id", "secret")),
+        ]);
+        assert_eq!(
+            chain.get_credentials().await?,
+            Credential::new("expired-id", "expired-secret")
+        );
+        assert_eq!(chain.selected(), Some(1));
+
+        expired.fail.store(true, Ordering::SeqCst);
+        assert_eq!(
+            chain.get_credentials().await?,
+            Credential::new("id", "secret")
+        );
+        assert_eq!(chain.selected(), Some(2));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn default_credential_chain_test() -> Result<()> {
+        let config_file = env::temp_dir().join(format!("aliyun-config-{}.json", Uuid::new_v4()));
+        let config = json!({
+            "current": "default",
+            "profiles": [
+                {
+                    "name": "default",
+                    "mode": "AK",
+                    "access_key_id": "profile-id",
+                    "access_key_secret": "profile-secret",
+                },
+            ],
+        });
+        fs::write(&config_file, config.to_string()).unwrap();
+        let config_file = config_file.to_str().unwrap();
+
+        // env vars take precedence over the config file.
+        let chain = DefaultCredentialChain::with_env(Env::from([
+            ("ALIBABA_CLOUD_ACCESS_KEY_ID", "env-id"),
+            ("ALIBABA_CLOUD_ACCESS_KEY_SECRET", "env-secret"),
+            ("ALIBABA_CLOUD_CONFIG_FILE", config_file),
+            ("ALIBABA_CLOUD_ECS_METADATA_DISABLED", "true"),
+        ]));
+        assert_eq!(
+            chain.get_credentials().await?,
+            Credential::new("env-id", "env-secret")
+        );
+
+        let chain = DefaultCredentialChain::with_env(Env::from([
+            ("ALIBABA_CLOUD_CONFIG_FILE", config_file),
+            ("ALIBABA_CLOUD_ECS_METADATA_DISABLED", "true"),
+        ]));
+        assert_eq!(
+            chain.get_credentials().await?,
+            Credential::new("profile-id", "profile-secret")
+        );
+
+        // RRSA and ECS RAM role are only in the chain when they are configured.
+        let chain = DefaultCredentialChain::with_env(Env::from([(
+            "ALIBABA_CLOUD_ECS_METADATA_DISABLED",
+            "true",
+        )]));
+        assert_eq!(chain.providers.len(), 2);
+        assert!(matches!(
+            chain.get_credentials().await,
+            Err(Error::InvalidCredential(_))
+        ));
+
+        let chain = DefaultCredentialChain::with_env(Env::from([
+            ("ALIBABA_CLOUD_ROLE_ARN", "acs:ram::123456:role/test-role"),
+            (
+                "ALIBABA_CLOUD_OIDC_PROVIDER_ARN",
+                "acs:ram::123456:oidc-provider/ack-rrsa",
+            ),
+            ("ALIBABA_CLOUD_OIDC_TOKEN_FILE", "/var/run/secrets/token"),
+        ]));
+        assert_eq!(chain.providers.len(), 4);
+
+        fs::remove_file(config_file).unwrap();
+
+        Ok(())
+    }
+}
@@ -0,0 +1,346 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use reqwest::{header::HeaderMap, Client, Response};
+use serde::Deserialize;
+use time::OffsetDateTime;
+
+use crate::client::{
+    credential::{Credential, CredentialCache, CredentialProvider, StsCredential},
+    error::{Error, Result},
+    transport::{new_request, HttpTransport, ReqwestTransport},
+};
+
+/// Default base url of ECS metadata service.
+const DEFAULT_METADATA_URL: &str = "http://100.100.100.200";
+const SECURITY_CREDENTIALS_PATH: &str = "/latest/meta-data/ram/security-credentials/";
+const METADATA_TOKEN_PATH: &str = "/latest/api/token";
+const METADATA_TOKEN_HEADER: &str = "x-aliyun-ecs-metadata-token";
+const METADATA_TOKEN_TTL_HEADER: &str = "x-aliyun-ecs-metadata-token-ttl-seconds";
+const METADATA_TOKEN_TTL_SECONDS: u64 = 21600;
+const METADATA_TIMEOUT: Duration = Duration::from_secs(5);
+/// The metadata service is only reachable on ECS, so give up connecting quickly elsewhere.
+const METADATA_CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SecurityCredentialsResponse {
+    code: String,
+    #[serde(flatten)]
+    credential: StsCredential,
+}
+
+/// The provider that gets credentials of the RAM role attached to the ECS instance.
+///
+/// The credentials are fetched from the ECS metadata service, with the IMDSv2 token
+/// handshake (security hardening mode).
+#[derive(Debug)]
+pub struct EcsRamRoleProvider {
+    /// The name of the RAM role, fetched from metadata service if not set.
+    role_name: Option<String>,
+    /// The base url of ECS metadata service.
+    metadata_url: String,
+    /// Whether to fail instead of falling back to IMDSv1 when the token handshake fails.
+    disable_imds_v1: bool,
+    /// The transport to call metadata service.
+    transport: Arc<dyn HttpTransport>,
+    /// The cached temporary credential.
+    cache: CredentialCache,
+}
+
+impl Default for EcsRamRoleProvider {
+    fn default() -> Self {
+        EcsRamRoleProvider {
+            role_name: None,
+            metadata_url: DEFAULT_METADATA_URL.to_string(),
+            disable_imds_v1: false,
+            transport: Arc::new(ReqwestTransport::new(
+                Client::builder()
+                    .connect_timeout(METADATA_CONNECT_TIMEOUT)
+                    .build()
+                    .unwrap_or_default(),
+            )),
+            cache: Default::default(),
+        }
+    }
+}
+
+impl EcsRamRoleProvider {
+    /// Create a provider which detects the RAM role attached to the instance.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the name of the RAM role.
+    pub fn role_name(mut self, role_name: impl Into<String>) -> Self {
+        self.role_name = Some(role_name.into());
+
+        self
+    }
+
+    /// Set the base url of ECS metadata service.
+    ///
+    /// Default is `http://100.100.100.200`.
+    pub fn metadata_url(mut self, metadata_url: impl Into<String>) -> Self {
+        self.metadata_url = metadata_url.into();
+
+        self
+    }
+
+    /// Fail instead of falling back to IMDSv1 when the metadata service rejects the token
+    /// request with a 4xx status. Other failures of the token request always fail.
+    pub fn disable_imds_v1(mut self, disable_imds_v1: bool) -> Self {
+        self.disable_imds_v1 = disable_imds_v1;
+
+        self
+    }
+
+    /// Set the transport to call metadata service.
+    ///
+    /// Default is [`ReqwestTransport`] which gives up connecting in 1 second.
+    pub fn transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+
+        self
+    }
+
+    /// Send a request to metadata service.
+    async fn send(&self, method: &str, path: &str, headers: HeaderMap) -> Result<Response> {
+        let url = format!("{}{}", self.metadata_url, path);
+        let request = new_request(method, &url, &[], headers, None)?;
+
+        self.transport
+            .execute(request, Some(METADATA_TIMEOUT))
+            .await
+    }
+
+    /// Fetch the metadata token for IMDSv2.
+    async fn metadata_token(&self) -> Result<Option<String>> {
+        let mut headers = HeaderMap::new();
+        headers.insert(METADATA_TOKEN_TTL_HEADER, METADATA_TOKEN_TTL_SECONDS.into());
+        // a timeout or other error of transport is not a reason to fallback to IMDSv1.
+        let response = self
+            .send("PUT", METADATA_TOKEN_PATH, headers)
+            .await
+            .map_err(|e| Error::InvalidCredential(format!("Cannot get metadata token: {e}")))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(Some(response.text().await?));
+        }
+
+        // only the metadata service which rejects the token request falls back to IMDSv1.
+        if !status.is_client_error() || self.disable_imds_v1 {
+            return Err(Error::InvalidCredential(format!(
+                "Cannot get metadata token: {status}"
+            )));
+        }
+
+        Ok(None)
+    }
+
+    /// Send a `GET` request to metadata service.
+    async fn get(&self, path: &str, token: Option<&str>) -> Result<String> {
+        let mut headers = HeaderMap::new();
+        if let Some(token) = token {
+            headers.insert(METADATA_TOKEN_HEADER, token.parse()?);
+        }
+
+        let response = self.send("GET", path, headers).await?;
+        if !response.status().is_success() {
+            return Err(Error::InvalidCredential(format!(
+                "Cannot get {path} from metadata service: {}",
+                response.status()
+            )));
+        }
+
+        Ok(response.text().await?)
+    }
+
+    /// Fetch a new temporary credential from metadata service.
+    async fn fetch_credential(&self) -> Result<(Credential, OffsetDateTime)> {
+        let token = self.metadata_token().await?;
+
+        let role_name = match self.role_name.as_ref() {
+            Some(role_name) => role_name.clone(),
+            None => self
+                .get(SECURITY_CREDENTIALS_PATH, token.as_deref())
+                .await?
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+        };
+        if role_name.is_empty() {
+            return Err(Error::InvalidCredential(
+                "No RAM role attached to the ECS instance".to_string(),
+            ));
+        }
+
+        let body = self
+            .get(
+                &format!("{SECURITY_CREDENTIALS_PATH}{role_name}"),
+                token.as_deref(),
+            )
+            .await?;
+        let response: SecurityCredentialsResponse = serde_json::from_str(&body)
+            .map_err(|e| Error::InvalidCredential(format!("Invalid security credentials: {e}")))?;
+        if response.code != "Success" {
+            return Err(Error::InvalidCredential(format!(
+                "Cannot get security credentials of role {role_name}: {}",
+                response.code
+            )));
+        }
+
+        response.credential.into_credential()
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for EcsRamRoleProvider {
+    async fn get_credentials(&self) -> Result<Credential> {
+        self.cache.get_or_refresh(|| self.fetch_credential()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use time::{format_description::well_known::Rfc3339, Duration};
+
+    use super::*;
+    use crate::client::mock::MockTransport;
+
+    fn security_credentials(expiration: OffsetDateTime) -> String {
+        json!({
+            "AccessKeyId": "STS.id",
+            "AccessKeySecret": "sts-secret",
+            "SecurityToken": "sts-token",
+            "Expiration": expiration.format(&Rfc3339).unwrap(),
+            "LastUpdated": "2023-07-15T00:00:00Z",
+            "Code": "Success",
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn ecs_ram_role_provider_test() -> Result<()> {
+        let transport = MockTransport::new()
+            .unsigned_host("100.100.100.200")
+            .on_path("PUT", "/latest/api/token", 200, "metadata-token")
+            .on_path(
+                "GET",
+                "/latest/meta-data/ram/security-credentials/",
+                200,
+                "test-role",
+            )
+            .on_path(
+                "GET",
+                "/latest/meta-data/ram/security-credentials/test-role",
+                200,
+                security_credentials(OffsetDateTime::now_utc() + Duration::hours(6)),
+            );
+
+        let provider = EcsRamRoleProvider::new().transport(Arc::new(transport.clone()));
+
+        for _ in 0..2 {
+            let credential = provider.get_credentials().await?;
+            assert_eq!(
+                credential,
+                Credential::new("STS.id", "sts-secret").with_security_token("sts-token")
+            );
+        }
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].url.host_str(), Some("100.100.100.200"));
+        assert_eq!(requests[0].headers[METADATA_TOKEN_TTL_HEADER], "21600");
+        for request in &requests[1..] {
+            assert_eq!(request.headers[METADATA_TOKEN_HEADER], "metadata-token");
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn ecs_ram_role_provider_imds_v1_test() -> Result<()> {
+        let transport = Arc::new(
+            MockTransport::new()
+                .unsigned_host("100.100.100.200")
+                .on_path("PUT", "/latest/api/token", 403, "{}")
+                .on_path(
+                    "GET",
+                    "/latest/meta-data/ram/security-credentials/test-role",
+                    200,
+                    security_credentials(OffsetDateTime::now_utc() + Duration::hours(6)),
+                ),
+        );
+
+        let provider = EcsRamRoleProvider::new()
+            .role_name("test-role")
+            .transport(transport.clone());
+        assert!(provider.get_credentials().await.is_ok());
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(!requests[1].headers.contains_key(METADATA_TOKEN_HEADER));
+
+        let provider = EcsRamRoleProvider::new()
+            .role_name("test-role")
+            .transport(transport.clone())
+            .disable_imds_v1(true);
+        assert!(matches!(
+            provider.get_credentials().await,
+            Err(Error::InvalidCredential(_))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn ecs_ram_role_provider_token_error_test() -> Result<()> {
+        let transport = Arc::new(
+            MockTransport::new()
+                .unsigned_host("100.100.100.200")
+                .on_path("PUT", "/latest/api/token", 500, "{}"),
+        );
+
+        let provider = EcsRamRoleProvider::new()
+            .role_name("test-role")
+            .transport(transport.clone());
+        assert!(matches!(
+            provider.get_credentials().await,
+            Err(Error::InvalidCredential(_))
+        ));
+        assert_eq!(transport.requests().len(), 1);
+
+        // a listener which never accepts, the token request times out.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let provider = EcsRamRoleProvider::new()
+            .role_name("test-role")
+            .metadata_url(format!("http://{}", listener.local_addr().unwrap()));
+        assert!(matches!(
+            provider.get_credentials().await,
+            Err(Error::InvalidCredential(_))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn ecs_ram_role_provider_unreachable_test() -> Result<()> {
+        // a port which nothing listens on, like the metadata service outside of ECS.
+        let address = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let provider = EcsRamRoleProvider::new()
+            .role_name("test-role")
+            .metadata_url(format!("http://{address}"));
+        assert!(matches!(
+            provider.get_credentials().await,
+            Err(Error::InvalidCredential(_))
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+use async_trait::async_trait;
+
+use crate::client::{
+    credential::{Credential, CredentialProvider, Env},
+    error::{Error, Result},
+};
+
+const ACCESS_KEY_ID_ENV: &str = "ALIBABA_CLOUD_ACCESS_KEY_ID";
+const ACCESS_KEY_SECRET_ENV: &str = "ALIBABA_CLOUD_ACCESS_KEY_SECRET";
+const SECURITY_TOKEN_ENV: &str = "ALIBABA_CLOUD_SECURITY_TOKEN";
+
+/// The provider that reads credentials from env vars:
+/// `ALIBABA_CLOUD_ACCESS_KEY_ID`, `ALIBABA_CLOUD_ACCESS_KEY_SECRET`
+/// and optional `ALIBABA_CLOUD_SECURITY_TOKEN`.
+///
+/// The env vars are read on every call, so they can be updated at runtime.
+#[derive(Clone, Debug, Default)]
+pub struct EnvironmentProvider {
+    /// The lookup of env vars.
+    env: Env,
+}
+
+impl EnvironmentProvider {
+    /// Create a env vars provider.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the lookup of env vars.
+    pub(crate) fn env(mut self, env: Env) -> Self {
+        self.env = env;
+
+        self
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for EnvironmentProvider {
+    async fn get_credentials(&self) -> Result<Credential> {
+        let var = |key: &str| {
+            self.env
+                .var(key)
+                .filter(|v| !v.is_empty())
+                .ok_or_else(|| Error::InvalidCredential(format!("Env var {key} is not set")))
+        };
+
+        let mut credential = Credential::new(var(ACCESS_KEY_ID_ENV)?, var(ACCESS_KEY_SECRET_ENV)?);
+        if let Ok(security_token) = var(SECURITY_TOKEN_ENV) {
+            credential = credential.with_security_token(security_token);
+        }
+
+        Ok(credential)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn environment_provider_test() -> Result<()> {
+        let provider = EnvironmentProvider::new().env(Env::from([
+            ("ALIBABA_CLOUD_ACCESS_KEY_ID", "id"),
+            ("ALIBABA_CLOUD_ACCESS_KEY_SECRET", "secret"),
+        ]));
+        assert_eq!(
+            provider.get_credentials().await?,
+            Credential::new("id", "secret")
+        );
+
+        let provider = EnvironmentProvider::new().env(Env::from([
+            ("ALIBABA_CLOUD_ACCESS_KEY_ID", "id"),
+            ("ALIBABA_CLOUD_ACCESS_KEY_SECRET", "secret"),
+            ("ALIBABA_CLOUD_SECURITY_TOKEN", "token"),
+        ]));
+        assert_eq!(
+            provider.get_credentials().await?,
+            Credential::new("id", "secret").with_security_token("token")
+        );
+
+        // empty values are treated as unset.
+        let provider = EnvironmentProvider::new().env(Env::from([
+            ("ALIBABA_CLOUD_ACCESS_KEY_ID", "id"),
+            ("ALIBABA_CLOUD_ACCESS_KEY_SECRET", ""),
+        ]));
+        assert!(matches!(
+            provider.get_credentials().await,
+            Err(Error::InvalidCredential(_))
+        ));
+
+        // the names of the legacy examples are not read.
+        let provider = EnvironmentProvider::new().env(Env::from([
+            ("ACCESS_KEY_ID", "id"),
+            ("ACCESS_KEY_SECRET", "secret"),
+        ]));
+        assert!(matches!(
+            provider.get_credentials().await,
+            Err(Error::InvalidCredential(_))
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,261 @@
+use std::{
+    env,
+    fmt::{self, Debug},
+    future::Future,
+    sync::Arc,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use futures::lock::Mutex;
+use serde::Deserialize;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use crate::client::error::{Error, Result};
+
+mod assume_role;
+mod chain;
+mod ecs_ram_role;
+mod environment;
+mod oidc;
+mod profile;
+
+pub use assume_role::AssumeRoleProvider;
+pub use chain::DefaultCredentialChain;
+pub use ecs_ram_role::EcsRamRoleProvider;
+pub use environment::EnvironmentProvider;
+pub use oidc::OidcRoleArnProvider;
+pub use profile::ProfileProvider;
+
+/// Refresh temporary credentials before they expire.
+const REFRESH_AHEAD: Duration = Duration::from_secs(180);
+
+/// The credential used to sign requests.
+///
+/// The `Debug` output redacts the access key secret and the security token.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Credential {
+    /// The access key id of aliyun developer account.
+    pub access_key_id: String,
+    /// The access key secret of aliyun developer account.
+    pub access_key_secret: String,
+    /// The security token of STS temporary credential.
+    pub security_token: Option<String>,
+}
+
+impl Credential {
+    /// Create a credential with the access key pair.
+    pub fn new(access_key_id: impl Into<String>, access_key_secret: impl Into<String>) -> Self {
+        Credential {
+            access_key_id: access_key_id.into(),
+            access_key_secret: access_key_secret.into(),
+            security_token: None,
+        }
+    }
+
+    /// Set the security token of STS temporary credential.
+    pub fn with_security_token(mut self, security_token: impl Into<String>) -> Self {
+        self.security_token = Some(security_token.into());
+
+        self
+    }
+}
+
+impl Debug for Credential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credential")
+            .field("access_key_id", &self.access_key_id)
+            .field("access_key_secret", &"<redacted>")
+            .field(
+                "security_token",
+                &self.security_token.as_ref().map(|_| "<redacted>"),
+            )
+            .finish()
+    }
+}
+
+/// Provide credentials for api clients.
+///
+/// Clients ask the provider for a credential every time a request is sent,
+/// so the provider can rotate keys without rebuilding clients.
+#[async_trait]
+pub trait CredentialProvider: Debug + Send + Sync {
+    /// Get the credential to sign the next request.
+    async fn get_credentials(&self) -> Result<Credential>;
+}
+
+/// The provider that always returns the same credential.
+#[derive(Clone, Debug)]
+pub struct StaticCredentialProvider {
+    credential: Credential,
+}
+
+impl StaticCredentialProvider {
+    /// Create a static provider with the access key pair.
+    pub fn new(access_key_id: impl Into<String>, access_key_secret: impl Into<String>) -> Self {
+        StaticCredentialProvider {
+            credential: Credential::new(access_key_id, access_key_secret),
+        }
+    }
+
+    /// Create a static provider with the STS temporary credential.
+    pub fn with_security_token(
+        access_key_id: impl Into<String>,
+        access_key_secret: impl Into<String>,
+        security_token: impl Into<String>,
+    ) -> Self {
+        StaticCredentialProvider {
+            credential: Credential::new(access_key_id, access_key_secret)
+                .with_security_token(security_token),
+        }
+    }
+}
+
+impl From<Credential> for StaticCredentialProvider {
+    fn from(credential: Credential) -> Self {
+        StaticCredentialProvider { credential }
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for StaticCredentialProvider {
+    async fn get_credentials(&self) -> Result<Credential> {
+        Ok(self.credential.clone())
+    }
+}
+
+/// The lookup of env vars used by the providers.
+///
+/// Default is the env vars of the process, tests replace it with fixed values.
+#[derive(Clone)]
+pub(crate) struct Env(Arc<EnvLookup>);
+
+/// The function to get the value of env var.
+type EnvLookup = dyn Fn(&str) -> Option<String> + Send + Sync;
+
+impl Env {
+    /// Create a lookup with the function.
+    pub(crate) fn new(lookup: impl Fn(&str) -> Option<String> + Send + Sync + 'static) -> Self {
+        Env(Arc::new(lookup))
+    }
+
+    /// Get the value of env var `key`.
+    pub(crate) fn var(&self, key: &str) -> Option<String> {
+        (self.0)(key)
+    }
+}
+
+impl Default for Env {
+    fn default() -> Self {
+        Env::new(|key| env::var(key).ok())
+    }
+}
+
+impl Debug for Env {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Env")
+    }
+}
+
+#[cfg(test)]
+impl<const N: usize> From<[(&str, &str); N]> for Env {
+    fn from(vars: [(&str, &str); N]) -> Self {
+        let vars: std::collections::HashMap<String, String> = vars
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+
+        Env::new(move |key| vars.get(key).cloned())
+    }
+}
+
+/// The temporary credential returned by STS and the ECS metadata service.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct StsCredential {
+    access_key_id: String,
+    access_key_secret: String,
+    security_token: String,
+    expiration: String,
+}
+
+impl StsCredential {
+    /// Convert into a credential and its expiration time.
+    pub(crate) fn into_credential(self) -> Result<(Credential, OffsetDateTime)> {
+        let expiration = OffsetDateTime::parse(&self.expiration, &Rfc3339).map_err(|e| {
+            Error::InvalidCredential(format!("Invalid expiration {}: {e}", self.expiration))
+        })?;
+        let credential = Credential::new(self.access_key_id, self.access_key_secret)
+            .with_security_token(self.security_token);
+
+        Ok((credential, expiration))
+    }
+}
+
+/// Cache a temporary credential until it is about to expire.
+#[derive(Debug, Default)]
+pub(crate) struct CredentialCache {
+    cached: Mutex<Option<(Credential, OffsetDateTime)>>,
+}
+
+impl CredentialCache {
+    /// Get the cached credential, or call `refresh` if it is missing or about to expire.
+    pub(crate) async fn get_or_refresh<F, Fut>(&self, refresh: F) -> Result<Credential>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(Credential, OffsetDateTime)>>,
+    {
+        let mut cached = self.cached.lock().await;
+        if let Some((credential, expiration)) = cached.as_ref() {
+            if OffsetDateTime::now_utc() + REFRESH_AHEAD < *expiration {
+                return Ok(credential.clone());
+            }
+        }
+
+        let (credential, expiration) = refresh().await?;
+        *cached = Some((credential.clone(), expiration));
+
+        Ok(credential)
+    }
+
+    /// Drop the cached credential, so it is refreshed on next use.
+    pub(crate) async fn invalidate(&self) {
+        *self.cached.lock().await = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn static_credential_provider_test() -> Result<()> {
+        let provider = StaticCredentialProvider::new("id", "secret");
+
+        let credential = provider.get_credentials().await?;
+
+        assert_eq!(credential, Credential::new("id", "secret"));
+        assert!(credential.security_token.is_none());
+
+        let provider = StaticCredentialProvider::with_security_token("id", "secret", "token");
+
+        let credential = provider.get_credentials().await?;
+
+        assert_eq!(credential.security_token.as_deref(), Some("token"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn credential_debug_test() {
+        let credential = Credential::new("id", "secret-value").with_security_token("token-value");
+        let provider =
+            StaticCredentialProvider::with_security_token("id", "secret-value", "token-value");
+
+        for debug in [format!("{:?}", credential), format!("{:?}", provider)] {
+            assert!(debug.contains("\"id\""));
+            assert!(!debug.contains("secret-value"));
+            assert!(!debug.contains("token-value"));
+        }
+    }
+}
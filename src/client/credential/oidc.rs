@@ -0,0 +1,301 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{Arc, OnceLock},
+    time::SystemTime,
+};
+
+use async_trait::async_trait;
+use futures::lock::Mutex;
+use serde::Deserialize;
+use time::OffsetDateTime;
+
+use crate::client::{
+    credential::{Credential, CredentialCache, CredentialProvider, Env, StsCredential},
+    error::{Error, Result},
+    rpc::RPClient,
+    transport::{HttpTransport, ReqwestTransport},
+};
+
+/// Default endpoint of STS service.
+const DEFAULT_ENDPOINT: &str = "https://sts.aliyuncs.com/";
+const DEFAULT_ROLE_SESSION_NAME: &str = "aliyun-openapi-core-rust-sdk";
+const DEFAULT_DURATION_SECONDS: u64 = 3600;
+
+/// Env vars injected by RRSA on ACK.
+const ROLE_ARN_ENV: &str = "ALIBABA_CLOUD_ROLE_ARN";
+const OIDC_PROVIDER_ARN_ENV: &str = "ALIBABA_CLOUD_OIDC_PROVIDER_ARN";
+const OIDC_TOKEN_FILE_ENV: &str = "ALIBABA_CLOUD_OIDC_TOKEN_FILE";
+const ROLE_SESSION_NAME_ENV: &str = "ALIBABA_CLOUD_ROLE_SESSION_NAME";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AssumeRoleWithOIDCResponse {
+    credentials: StsCredential,
+}
+
+/// The provider that assumes a RAM role with an OIDC token by calling STS `AssumeRoleWithOIDC`.
+///
+/// It is used by the RRSA feature of ACK, the OIDC token file is read again and the
+/// credential is refreshed when the token file is rotated.
+#[derive(Debug)]
+pub struct OidcRoleArnProvider {
+    /// The api endpoint of STS service.
+    endpoint: String,
+    /// The ARN of the role to assume.
+    role_arn: String,
+    /// The ARN of the OIDC identity provider.
+    oidc_provider_arn: String,
+    /// The path of the OIDC token file.
+    oidc_token_file: PathBuf,
+    /// The session name of the assumed role.
+    role_session_name: String,
+    /// The policy to further restrict the assumed role.
+    policy: Option<String>,
+    /// The valid period of the temporary credential.
+    duration_seconds: u64,
+    /// The transport to call STS.
+    transport: Arc<dyn HttpTransport>,
+    /// The client to call STS, built on the first refresh and shared by the later ones.
+    client: OnceLock<RPClient>,
+    /// The modified time of the token file when the credential is cached.
+    token_modified: Mutex<Option<SystemTime>>,
+    /// The cached temporary credential.
+    cache: CredentialCache,
+}
+
+impl OidcRoleArnProvider {
+    /// Create a provider to assume the role `role_arn` with the OIDC token file.
+    pub fn new(
+        role_arn: impl Into<String>,
+        oidc_provider_arn: impl Into<String>,
+        oidc_token_file: impl Into<PathBuf>,
+    ) -> Self {
+        OidcRoleArnProvider {
+            endpoint: DEFAULT_ENDPOINT.to_string(),
+            role_arn: role_arn.into(),
+            oidc_provider_arn: oidc_provider_arn.into(),
+            oidc_token_file: oidc_token_file.into(),
+            role_session_name: DEFAULT_ROLE_SESSION_NAME.to_string(),
+            policy: None,
+            duration_seconds: DEFAULT_DURATION_SECONDS,
+            transport: Arc::new(ReqwestTransport::default()),
+            client: OnceLock::new(),
+            token_modified: Default::default(),
+            cache: Default::default(),
+        }
+    }
+
+    /// Create a provider from the env vars injected by RRSA:
+    /// `ALIBABA_CLOUD_ROLE_ARN`, `ALIBABA_CLOUD_OIDC_PROVIDER_ARN`,
+    /// `ALIBABA_CLOUD_OIDC_TOKEN_FILE` and optional `ALIBABA_CLOUD_ROLE_SESSION_NAME`.
+    pub fn from_env() -> Result<Self> {
+        Self::from_env_vars(&Env::default())
+    }
+
+    /// Create a provider from the env vars of RRSA in `env`.
+    pub(crate) fn from_env_vars(env: &Env) -> Result<Self> {
+        let var = |key: &str| {
+            env.var(key)
+                .ok_or_else(|| Error::InvalidCredential(format!("Env var {key} is not set")))
+        };
+
+        let mut provider = Self::new(
+            var(ROLE_ARN_ENV)?,
+            var(OIDC_PROVIDER_ARN_ENV)?,
+            var(OIDC_TOKEN_FILE_ENV)?,
+        );
+        if let Some(role_session_name) = env.var(ROLE_SESSION_NAME_ENV) {
+            provider = provider.role_session_name(role_session_name);
+        }
+
+        Ok(provider)
+    }
+
+    /// Set the api endpoint of STS service.
+    ///
+    /// Default is `https://sts.aliyuncs.com/`.
+    pub fn endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+
+        self
+    }
+
+    /// Set the session name of the assumed role.
+    pub fn role_session_name(mut self, role_session_name: impl Into<String>) -> Self {
+        self.role_session_name = role_session_name.into();
+
+        self
+    }
+
+    /// Set the policy to further restrict the assumed role.
+    pub fn policy(mut self, policy: impl Into<String>) -> Self {
+        self.policy = Some(policy.into());
+
+        self
+    }
+
+    /// Set the valid period of the temporary credential in seconds.
+    ///
+    /// Default is 3600 seconds.
+    pub fn duration_seconds(mut self, duration_seconds: u64) -> Self {
+        self.duration_seconds = duration_seconds;
+
+        self
+    }
+
+    /// Set the transport to call STS.
+    ///
+    /// Default is [`ReqwestTransport`].
+    pub fn transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+
+        self
+    }
+
+    /// Call STS `AssumeRoleWithOIDC` to get a new temporary credential.
+    async fn assume_role_with_oidc(&self) -> Result<(Credential, OffsetDateTime)> {
+        let oidc_token = fs::read_to_string(&self.oidc_token_file).map_err(|e| {
+            Error::InvalidCredential(format!(
+                "Cannot read OIDC token file {}: {e}",
+                self.oidc_token_file.display()
+            ))
+        })?;
+
+        let mut queries = vec![
+            ("RoleArn".to_string(), self.role_arn.clone()),
+            (
+                "OIDCProviderArn".to_string(),
+                self.oidc_provider_arn.clone(),
+            ),
+            ("OIDCToken".to_string(), oidc_token.trim().to_string()),
+            (
+                "RoleSessionName".to_string(),
+                self.role_session_name.clone(),
+            ),
+            (
+                "DurationSeconds".to_string(),
+                self.duration_seconds.to_string(),
+            ),
+        ];
+        if let Some(policy) = self.policy.as_ref() {
+            queries.push(("Policy".to_string(), policy.clone()));
+        }
+
+        let client = self.client.get_or_init(|| {
+            RPClient::new("", "", self.endpoint.as_str())
+                .transport(self.transport.clone())
+                .version("2015-04-01")
+        });
+        let response = client
+            .clone()
+            .post("AssumeRoleWithOIDC")
+            .query(queries)
+            .anonymous()
+            .json::<AssumeRoleWithOIDCResponse>()
+            .await?;
+
+        response.credentials.into_credential()
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for OidcRoleArnProvider {
+    async fn get_credentials(&self) -> Result<Credential> {
+        // drop the cached credential if the token file is rotated.
+        let modified = fs::metadata(&self.oidc_token_file)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        {
+            let mut token_modified = self.token_modified.lock().await;
+            if *token_modified != modified {
+                self.cache.invalidate().await;
+                *token_modified = modified;
+            }
+        }
+
+        self.cache
+            .get_or_refresh(|| self.assume_role_with_oidc())
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env,
+        fs::File,
+        time::{Duration as StdDuration, SystemTime},
+    };
+
+    use serde_json::json;
+    use time::{format_description::well_known::Rfc3339, Duration};
+    use url::form_urlencoded;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::client::mock::MockTransport;
+
+    fn write_token(path: &PathBuf, token: &str, modified: SystemTime) {
+        fs::write(path, token).unwrap();
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn oidc_role_arn_provider_test() -> Result<()> {
+        let response = json!({
+            "RequestId": "request-id",
+            "Credentials": {
+                "AccessKeyId": "STS.id",
+                "AccessKeySecret": "sts-secret",
+                "SecurityToken": "sts-token",
+                "Expiration": (OffsetDateTime::now_utc() + Duration::hours(1)).format(&Rfc3339).unwrap(),
+            }
+        });
+        let transport = MockTransport::new()
+            .anonymous_action("AssumeRoleWithOIDC")
+            .on_action("AssumeRoleWithOIDC", 200, response.to_string());
+        let oidc_token = |index: usize| {
+            let body = transport.requests()[index].body.clone().unwrap_or_default();
+            form_urlencoded::parse(&body)
+                .find(|(k, _)| k == "OIDCToken")
+                .map(|(_, v)| v.into_owned())
+        };
+
+        let token_file = env::temp_dir().join(format!("oidc-token-{}", Uuid::new_v4()));
+        let now = SystemTime::now();
+        write_token(&token_file, "first-token", now);
+
+        let provider = OidcRoleArnProvider::new(
+            "acs:ram::1:role/test",
+            "acs:ram::1:oidc-provider/test",
+            &token_file,
+        )
+        .transport(Arc::new(transport.clone()));
+
+        let credential = provider.get_credentials().await?;
+        assert_eq!(credential.security_token.as_deref(), Some("sts-token"));
+        provider.get_credentials().await?;
+        assert_eq!(transport.requests().len(), 1);
+        assert_eq!(oidc_token(0).as_deref(), Some("first-token"));
+
+        // rotate token file.
+        write_token(
+            &token_file,
+            "second-token",
+            now + StdDuration::from_secs(60),
+        );
+        provider.get_credentials().await?;
+        assert_eq!(transport.requests().len(), 2);
+        assert_eq!(oidc_token(1).as_deref(), Some("second-token"));
+
+        fs::remove_file(&token_file).unwrap();
+
+        Ok(())
+    }
+}
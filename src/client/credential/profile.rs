@@ -0,0 +1,493 @@
+use std::{fs, path::PathBuf, sync::Arc};
+
+use async_trait::async_trait;
+use futures::lock::Mutex;
+use serde::Deserialize;
+
+use crate::client::{
+    credential::{
+        AssumeRoleProvider, Credential, CredentialProvider, EcsRamRoleProvider, Env,
+        StaticCredentialProvider,
+    },
+    error::{Error, Result},
+    transport::HttpTransport,
+};
+
+const CONFIG_FILE_ENV: &str = "ALIBABA_CLOUD_CONFIG_FILE";
+const PROFILE_ENV: &str = "ALIBABA_CLOUD_PROFILE";
+
+/// The max depth of `source_profile` in `ChainableRamRoleArn` mode.
+const MAX_SOURCE_PROFILE_DEPTH: usize = 8;
+
+/// The config file of aliyun CLI.
+#[derive(Debug, Default, Deserialize)]
+struct Config {
+    #[serde(default)]
+    current: String,
+    #[serde(default)]
+    profiles: Vec<Profile>,
+}
+
+/// The profile in the config file of aliyun CLI.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+struct Profile {
+    name: String,
+    mode: String,
+    access_key_id: String,
+    access_key_secret: String,
+    sts_token: String,
+    ram_role_name: String,
+    ram_role_arn: String,
+    ram_session_name: String,
+    source_profile: String,
+    expired_seconds: u64,
+    sts_region: String,
+    region_id: String,
+}
+
+impl Config {
+    /// Find the profile by name.
+    fn profile(&self, name: &str) -> Result<&Profile> {
+        self.profiles
+            .iter()
+            .find(|profile| profile.name == name)
+            .ok_or_else(|| Error::InvalidCredential(format!("Cannot find profile {name}")))
+    }
+
+    /// Build the credential provider of the profile.
+    fn provider(
+        &self,
+        name: &str,
+        sts_endpoint: Option<&str>,
+        transport: Option<&Arc<dyn HttpTransport>>,
+        depth: usize,
+    ) -> Result<Arc<dyn CredentialProvider>> {
+        if depth > MAX_SOURCE_PROFILE_DEPTH {
+            return Err(Error::InvalidCredential(format!(
+                "Too deep source profile chain at profile {name}"
+            )));
+        }
+
+        let profile = self.profile(name)?;
+        let provider: Arc<dyn CredentialProvider> = match profile.mode.as_str() {
+            "AK" => Arc::new(StaticCredentialProvider::new(
+                &profile.access_key_id,
+                &profile.access_key_secret,
+            )),
+            "StsToken" => Arc::new(StaticCredentialProvider::with_security_token(
+                &profile.access_key_id,
+                &profile.access_key_secret,
+                &profile.sts_token,
+            )),
+            "RamRoleArn" => {
+                let source = Arc::new(StaticCredentialProvider::new(
+                    &profile.access_key_id,
+                    &profile.access_key_secret,
+                ));
+                Arc::new(profile.assume_role_provider(source, sts_endpoint, transport))
+            }
+            "EcsRamRole" => {
+                let mut provider = EcsRamRoleProvider::new();
+                if !profile.ram_role_name.is_empty() {
+                    provider = provider.role_name(&profile.ram_role_name);
+                }
+                if let Some(transport) = transport {
+                    provider = provider.transport(transport.clone());
+                }
+                Arc::new(provider)
+            }
+            "ChainableRamRoleArn" => {
+                let source =
+                    self.provider(&profile.source_profile, sts_endpoint, transport, depth + 1)?;
+                Arc::new(profile.assume_role_provider(source, sts_endpoint, transport))
+            }
+            mode => {
+                return Err(Error::InvalidCredential(format!(
+                    "Unsupported mode {mode} of profile {name}"
+                )))
+            }
+        };
+
+        Ok(provider)
+    }
+}
+
+impl Profile {
+    /// Build the provider to assume the RAM role of the profile.
+    fn assume_role_provider(
+        &self,
+        source: Arc<dyn CredentialProvider>,
+        sts_endpoint: Option<&str>,
+        transport: Option<&Arc<dyn HttpTransport>>,
+    ) -> AssumeRoleProvider {
+        let mut provider = AssumeRoleProvider::new(source, &self.ram_role_arn);
+        if let Some(sts_endpoint) = sts_endpoint {
+            provider = provider.endpoint(sts_endpoint);
+        } else if !self.sts_region.is_empty() {
+            provider = provider.endpoint(format!("https://sts.{}.aliyuncs.com/", self.sts_region));
+        }
+        if !self.ram_session_name.is_empty() {
+            provider = provider.role_session_name(&self.ram_session_name);
+        }
+        if self.expired_seconds > 0 {
+            provider = provider.duration_seconds(self.expired_seconds);
+        }
+        if let Some(transport) = transport {
+            provider = provider.transport(transport.clone());
+        }
+
+        provider
+    }
+}
+
+/// The provider that reads credentials from the config file of aliyun CLI.
+///
+/// The config file is `~/.aliyun/config.json` by default, or the path in env var
+/// `ALIBABA_CLOUD_CONFIG_FILE`. The profile is selected by env var `ALIBABA_CLOUD_PROFILE`,
+/// or the current profile of aliyun CLI.
+///
+/// The supported modes are `AK`, `StsToken`, `RamRoleArn`, `EcsRamRole` and
+/// `ChainableRamRoleArn`.
+#[derive(Debug, Default)]
+pub struct ProfileProvider {
+    /// The path of the config file.
+    config_file: Option<PathBuf>,
+    /// The name of the profile.
+    profile: Option<String>,
+    /// The api endpoint of STS service to assume RAM roles.
+    sts_endpoint: Option<String>,
+    /// The transport to call STS and ECS metadata service.
+    transport: Option<Arc<dyn HttpTransport>>,
+    /// The provider built from the profile.
+    provider: Mutex<Option<Arc<dyn CredentialProvider>>>,
+    /// The lookup of env vars.
+    env: Env,
+}
+
+impl ProfileProvider {
+    /// Create a provider with the default config file and profile.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the path of the config file.
+    pub fn config_file(mut self, config_file: impl Into<PathBuf>) -> Self {
+        self.config_file = Some(config_file.into());
+
+        self
+    }
+
+    /// Set the name of the profile.
+    pub fn profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = Some(profile.into());
+
+        self
+    }
+
+    /// Set the api endpoint of STS service to assume RAM roles.
+    ///
+    /// Default is `https://sts.aliyuncs.com/`, or the endpoint of `sts_region` in the profile.
+    pub fn sts_endpoint(mut self, sts_endpoint: impl Into<String>) -> Self {
+        self.sts_endpoint = Some(sts_endpoint.into());
+
+        self
+    }
+
+    /// Set the transport to call STS and ECS metadata service.
+    ///
+    /// Default is the transport of [`AssumeRoleProvider`] and [`EcsRamRoleProvider`].
+    pub fn transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = Some(transport);
+
+        self
+    }
+
+    /// Set the lookup of env vars.
+    pub(crate) fn env(mut self, env: Env) -> Self {
+        self.env = env;
+
+        self
+    }
+
+    /// Get the region id of the profile.
+    ///
+    /// Returns `None` if the region is not configured. The clients created by `from_profile`
+    /// don't use the region, so build the endpoint with it if needed.
+    pub fn region_id(&self) -> Result<Option<String>> {
+        let config = self.load_config()?;
+        let profile = config.profile(&self.profile_name(&config))?;
+
+        Ok(Some(profile.region_id.clone()).filter(|region_id| !region_id.is_empty()))
+    }
+
+    /// Get the path of the config file.
+    fn config_path(&self) -> Result<PathBuf> {
+        if let Some(config_file) = self.config_file.as_ref() {
+            return Ok(config_file.clone());
+        }
+        if let Some(config_file) = self.env.var(CONFIG_FILE_ENV) {
+            return Ok(config_file.into());
+        }
+
+        self.env
+            .var("HOME")
+            .or_else(|| self.env.var("USERPROFILE"))
+            .map(|home| PathBuf::from(home).join(".aliyun").join("config.json"))
+            .ok_or_else(|| Error::InvalidCredential("Cannot find home directory".to_string()))
+    }
+
+    /// Load the config file.
+    fn load_config(&self) -> Result<Config> {
+        let path = self.config_path()?;
+        let content = fs::read_to_string(&path).map_err(|e| {
+            Error::InvalidCredential(format!("Cannot read config file {}: {e}", path.display()))
+        })?;
+
+        serde_json::from_str(&content).map_err(|e| {
+            Error::InvalidCredential(format!("Invalid config file {}: {e}", path.display()))
+        })
+    }
+
+    /// Get the name of the selected profile.
+    fn profile_name(&self, config: &Config) -> String {
+        self.profile
+            .clone()
+            .or_else(|| self.env.var(PROFILE_ENV))
+            .unwrap_or_else(|| config.current.clone())
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for ProfileProvider {
+    async fn get_credentials(&self) -> Result<Credential> {
+        let provider = {
+            let mut provider = self.provider.lock().await;
+            match provider.as_ref() {
+                Some(provider) => provider.clone(),
+                None => {
+                    let config = self.load_config()?;
+                    let inner = config.provider(
+                        &self.profile_name(&config),
+                        self.sts_endpoint.as_deref(),
+                        self.transport.as_ref(),
+                        0,
+                    )?;
+                    *provider = Some(inner.clone());
+                    inner
+                }
+            }
+        };
+
+        provider.get_credentials().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use serde_json::json;
+    use time::{format_description::well_known::Rfc3339, Duration, OffsetDateTime};
+    use url::form_urlencoded;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::client::mock::MockTransport;
+
+    /// The temporary credential in the response of STS and ECS metadata service.
+    fn sts_credentials() -> serde_json::Value {
+        json!({
+            "AccessKeyId": "STS.id",
+            "AccessKeySecret": "sts-secret",
+            "SecurityToken": "sts-token",
+            "Expiration": (OffsetDateTime::now_utc() + Duration::hours(1))
+                .format(&Rfc3339)
+                .unwrap(),
+            "LastUpdated": "2023-07-15T00:00:00Z",
+            "Code": "Success",
+        })
+    }
+
+    fn assume_role_response() -> String {
+        json!({
+            "RequestId": "request-id",
+            "Credentials": sts_credentials(),
+        })
+        .to_string()
+    }
+
+    fn write_config(config: serde_json::Value) -> PathBuf {
+        let config_file = env::temp_dir().join(format!("aliyun-config-{}.json", Uuid::new_v4()));
+        fs::write(&config_file, config.to_string()).unwrap();
+
+        config_file
+    }
+
+    /// Get the params of the form body of request.
+    fn form_params(body: Option<bytes::Bytes>) -> Vec<(String, String)> {
+        form_urlencoded::parse(&body.unwrap_or_default())
+            .into_owned()
+            .collect()
+    }
+
+    fn sts_credential() -> Credential {
+        Credential::new("STS.id", "sts-secret").with_security_token("sts-token")
+    }
+
+    #[tokio::test]
+    async fn profile_provider_test() -> Result<()> {
+        let transport = MockTransport::new()
+            .access_key("STS.source", "source-secret")
+            .on_action("AssumeRole", 200, assume_role_response());
+
+        let config_file = write_config(json!({
+            "current": "default",
+            "profiles": [
+                {
+                    "name": "default",
+                    "mode": "AK",
+                    "access_key_id": "id",
+                    "access_key_secret": "secret",
+                    "region_id": "cn-hangzhou",
+                },
+                {
+                    "name": "sts",
+                    "mode": "StsToken",
+                    "access_key_id": "STS.source",
+                    "access_key_secret": "source-secret",
+                    "sts_token": "source-token",
+                },
+                {
+                    "name": "chain",
+                    "mode": "ChainableRamRoleArn",
+                    "source_profile": "sts",
+                    "ram_role_arn": "acs:ram::1:role/test",
+                },
+            ]
+        }));
+
+        let provider = ProfileProvider::new().config_file(&config_file);
+        assert_eq!(
+            provider.get_credentials().await?,
+            Credential::new("id", "secret")
+        );
+        assert_eq!(provider.region_id()?.as_deref(), Some("cn-hangzhou"));
+
+        let provider = ProfileProvider::new()
+            .config_file(&config_file)
+            .profile("sts");
+        assert_eq!(provider.region_id()?, None);
+
+        let provider = ProfileProvider::new()
+            .config_file(&config_file)
+            .profile("chain")
+            .transport(Arc::new(transport.clone()));
+        for _ in 0..2 {
+            assert_eq!(provider.get_credentials().await?, sts_credential());
+        }
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        let params = form_params(requests[0].body.clone());
+        for param in [
+            ("AccessKeyId", "STS.source"),
+            ("SecurityToken", "source-token"),
+            ("RoleArn", "acs:ram::1:role/test"),
+        ] {
+            assert!(params.contains(&(param.0.to_string(), param.1.to_string())));
+        }
+
+        fs::remove_file(&config_file).unwrap();
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn profile_provider_ram_role_arn_test() -> Result<()> {
+        let transport = MockTransport::new().access_key("id", "secret").on_action(
+            "AssumeRole",
+            200,
+            assume_role_response(),
+        );
+        let config_file = write_config(json!({
+            "current": "ram",
+            "profiles": [
+                {
+                    "name": "ram",
+                    "mode": "RamRoleArn",
+                    "access_key_id": "id",
+                    "access_key_secret": "secret",
+                    "ram_role_arn": "acs:ram::1:role/test",
+                    "ram_session_name": "session",
+                    "expired_seconds": 900,
+                    "sts_region": "cn-shanghai",
+                },
+            ]
+        }));
+
+        let provider = ProfileProvider::new()
+            .config_file(&config_file)
+            .transport(Arc::new(transport.clone()));
+        assert_eq!(provider.get_credentials().await?, sts_credential());
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(
+            requests[0].url.host_str(),
+            Some("sts.cn-shanghai.aliyuncs.com")
+        );
+        let params = form_params(requests[0].body.clone());
+        for param in [
+            ("AccessKeyId", "id"),
+            ("RoleArn", "acs:ram::1:role/test"),
+            ("RoleSessionName", "session"),
+            ("DurationSeconds", "900"),
+        ] {
+            assert!(params.contains(&(param.0.to_string(), param.1.to_string())));
+        }
+
+        fs::remove_file(&config_file).unwrap();
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn profile_provider_ecs_ram_role_test() -> Result<()> {
+        let transport = MockTransport::new()
+            .unsigned_host("100.100.100.200")
+            .on_path("PUT", "/latest/api/token", 200, "metadata-token")
+            .on_path(
+                "GET",
+                "/latest/meta-data/ram/security-credentials/test-role",
+                200,
+                sts_credentials().to_string(),
+            );
+        let config_file = write_config(json!({
+            "current": "ecs",
+            "profiles": [
+                {
+                    "name": "ecs",
+                    "mode": "EcsRamRole",
+                    "ram_role_name": "test-role",
+                },
+            ]
+        }));
+
+        let provider = ProfileProvider::new()
+            .config_file(&config_file)
+            .transport(Arc::new(transport.clone()));
+        assert_eq!(provider.get_credentials().await?, sts_credential());
+
+        // the role name of profile is used without fetching it from metadata service.
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[1].url.path(),
+            "/latest/meta-data/ram/security-credentials/test-role"
+        );
+
+        fs::remove_file(&config_file).unwrap();
+
+        Ok(())
+    }
+}
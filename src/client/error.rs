@@ -1,7 +1,10 @@
+use std::time::Duration;
+
 use reqwest::{self, header::InvalidHeaderValue};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum Error {
     #[error("Reqwest error: {0}")]
     Reqwest(#[from] reqwest::Error),
@@ -12,6 +15,15 @@ pub enum Error {
     #[error("Request error: {0}")]
     InvalidRequest(String),
 
+    #[error("Credential error: {0}")]
+    InvalidCredential(String),
+
+    #[error("Circuit breaker is open for endpoint: {0}")]
+    CircuitOpen(String),
+
+    #[error("Request timed out after {0:?}")]
+    Timeout(Duration),
+
     #[error("Request id: {request_id}, Error code: {error_code}, Error message: {error_message}")]
     InvalidResponse {
         request_id: String,
@@ -1,16 +1,29 @@
-use std::{collections::HashMap, time::Duration};
+use std::{collections::HashMap, error::Error as StdError, sync::Arc, time::Duration};
 
-use hmac::{Hmac, Mac};
+use bytes::Bytes;
+use futures::TryStream;
 use md5::{Digest, Md5};
 use reqwest::{
-    header::{HeaderMap, HeaderValue},
-    ClientBuilder, Response,
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Client, Response,
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use sha1::Sha1;
 use time::{macros::format_description, OffsetDateTime};
 
-use crate::client::error::{Error, Result};
+use crate::client::{
+    body::Body,
+    clock::{is_clock_skew_error, Clock, ClockSkew, SystemClock},
+    credential::{
+        Credential, CredentialProvider, DefaultCredentialChain, ProfileProvider,
+        StaticCredentialProvider,
+    },
+    error::{Error, Result},
+    middleware::{Middleware, RequestContext},
+    replace_headers,
+    retry::RetryPolicy,
+    signature::{HmacSha1Signer, Signer},
+    transport::{new_request, HttpTransport, ReqwestTransport},
+};
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -25,19 +38,16 @@ pub struct LogServiceError {
 const AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
 const DEFAULT_HEADER: &[(&str, &str)] = &[
     ("x-log-apiversion", "0.6.0"),
-    ("x-log-signaturemethod", "hmac-sha1"),
     ("user-agent", AGENT),
     ("x-sdk-client", AGENT),
 ];
 
-type HamcSha1 = Hmac<Sha1>;
-
 /// Config for request.
 #[derive(Clone, Debug, Default)]
 struct Request {
     method: String,
     uri: String,
-    body: Option<String>,
+    body: Option<Body>,
     query: Vec<(String, String)>,
     headers: HeaderMap,
     project: Option<String>,
@@ -46,12 +56,20 @@ struct Request {
 
 #[derive(Clone, Debug)]
 pub struct LogServiceClient {
-    /// The access key id of aliyun developer account.
-    access_key_id: String,
-    /// The access key secret of aliyun developer account.
-    access_key_secret: String,
+    /// The provider of credentials to sign requests.
+    credential_provider: Arc<dyn CredentialProvider>,
     /// The api endpoint of aliyun api service (need start with http:// or https://).
     endpoint: String,
+    /// The source of current time to sign requests.
+    clock: Arc<dyn Clock>,
+    /// The offset between local clock and server clock, shared by clones.
+    clock_skew: ClockSkew,
+    /// The transport to send requests, shared by clones to reuse connections.
+    transport: Arc<dyn HttpTransport>,
+    /// The policy to retry failed requests.
+    retry_policy: RetryPolicy,
+    /// The middlewares to run around every attempt of request.
+    middlewares: Vec<Arc<dyn Middleware>>,
     /// The config of http request.
     request: Request,
 }
@@ -62,11 +80,45 @@ impl LogServiceClient {
         access_key_id: impl Into<String>,
         access_key_secret: impl Into<String>,
         endpoint: impl Into<String>,
+    ) -> Self {
+        Self::from_provider(
+            Arc::new(StaticCredentialProvider::new(
+                access_key_id,
+                access_key_secret,
+            )),
+            endpoint,
+        )
+    }
+
+    /// Create a api client with the default credential chain.
+    ///
+    /// See [`DefaultCredentialChain`] for where the credential is loaded from.
+    pub fn from_env(endpoint: impl Into<String>) -> Self {
+        Self::from_provider(Arc::new(DefaultCredentialChain::new()), endpoint)
+    }
+
+    /// Create a api client with the profile of aliyun CLI.
+    ///
+    /// See [`ProfileProvider`] for the supported modes of profile. Only the credential is
+    /// read from the profile, the region of log service is a part of `endpoint`, read the `region_id` of the profile by
+    /// [`ProfileProvider::region_id`] to build it if needed.
+    pub fn from_profile(profile: impl Into<String>, endpoint: impl Into<String>) -> Self {
+        Self::from_provider(Arc::new(ProfileProvider::new().profile(profile)), endpoint)
+    }
+
+    /// Create a api client with the credential provider.
+    pub fn from_provider(
+        credential_provider: Arc<dyn CredentialProvider>,
+        endpoint: impl Into<String>,
     ) -> Self {
         LogServiceClient {
-            access_key_id: access_key_id.into(),
-            access_key_secret: access_key_secret.into(),
+            credential_provider,
             endpoint: endpoint.into(),
+            clock: Arc::new(SystemClock),
+            clock_skew: Default::default(),
+            transport: Arc::new(ReqwestTransport::default()),
+            retry_policy: RetryPolicy::disabled(),
+            middlewares: Vec::new(),
             request: Default::default(),
         }
     }
@@ -95,7 +147,7 @@ impl LogServiceClient {
         self.request("POST".to_string(), uri.into())
     }
 
-    /// Set queries for request.
+    /// Set queries for request, the queries set before are replaced.
     ///
     /// Returns a `Self` for send request.
     pub fn query(mut self, queries: impl Into<Vec<(String, String)>>) -> Self {
@@ -104,38 +156,113 @@ impl LogServiceClient {
         self
     }
 
+    /// Add a query for request. If the query `key` is set, its value is replaced.
+    ///
+    /// Returns a `Self` for send request.
+    pub fn add_query(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let (key, value) = (key.into(), value.into());
+        match self.request.query.iter_mut().find(|(k, _)| *k == key) {
+            Some(query) => query.1 = value,
+            None => self.request.query.push((key, value)),
+        }
+
+        self
+    }
+
+    /// Add a query for request if the `value` is `Some`, see [`Self::add_query`].
+    ///
+    /// Returns a `Self` for send request.
+    pub fn query_opt(self, key: impl Into<String>, value: Option<impl Into<String>>) -> Self {
+        match value {
+            Some(value) => self.add_query(key, value),
+            None => self,
+        }
+    }
+
     /// Set body for request.
     ///
     /// Returns a `Self` for send request.
-    pub fn body(mut self, body: impl Into<String>) -> Result<Self> {
+    pub fn body(self, body: impl Into<String>) -> Result<Self> {
+        self.binary_body(body.into())
+    }
+
+    /// Set binary body for request, such as images, protobuf or compressed data.
+    ///
+    /// Returns a `Self` for send request.
+    pub fn binary_body(mut self, body: impl Into<Bytes>) -> Result<Self> {
         // compute body length and md5.
         let body = body.into();
         let mut hasher = Md5::new();
-        hasher.update(body.as_bytes());
+        hasher.update(&body);
         let md5_result = hasher.finalize();
 
         // update headers.
+        self.content_headers(body.len() as u64, &md5_result)?;
+
+        // store body bytes.
+        self.request.body = Some(Body::Bytes(body));
+
+        Ok(self)
+    }
+
+    /// Set streaming body for request, so a large body is not held in memory.
+    ///
+    /// The `stream` is called to create the body for every attempt of request, and the
+    /// `content_length` and the MD5 digest `content_md5` of body must be computed in advance.
+    ///
+    /// Returns a `Self` for send request.
+    pub fn stream_body<F, S>(
+        mut self,
+        stream: F,
+        content_length: u64,
+        content_md5: [u8; 16],
+    ) -> Result<Self>
+    where
+        F: Fn() -> S + Send + Sync + 'static,
+        S: TryStream + Send + Sync + 'static,
+        S::Error: Into<Box<dyn StdError + Send + Sync>>,
+        Bytes: From<S::Ok>,
+    {
+        self.content_headers(content_length, &content_md5)?;
+        self.request.body = Some(Body::stream(stream));
+
+        Ok(self)
+    }
+
+    /// Set the `content-length` and `content-md5` headers of body.
+    fn content_headers(&mut self, content_length: u64, content_md5: &[u8]) -> Result<()> {
         self.request
             .headers
-            .insert("content-length", body.len().to_string().parse()?);
+            .insert("content-length", content_length.to_string().parse()?);
         self.request.headers.insert(
             "content-md5",
-            base16ct::upper::encode_string(&md5_result).parse()?,
+            base16ct::upper::encode_string(content_md5).parse()?,
         );
 
-        // store body string.
-        self.request.body = Some(body);
-
-        Ok(self)
+        Ok(())
     }
 
-    /// Set header for request.
+    /// Set header for request, the headers set before are replaced, except the
+    /// `content-length`, `content-md5` and `content-type` headers set by the body methods,
+    /// which are kept unless they are in `headers`.
     ///
     /// Returns a `Self` for send request.
     pub fn header(mut self, headers: impl Into<HashMap<String, String>>) -> Result<Self> {
-        self.request.headers = (&headers.into())
+        let headers = (&headers.into())
             .try_into()
             .map_err(|e| Error::InvalidRequest(format!("Cannot parse header: {}", e)))?;
+        replace_headers(&mut self.request.headers, headers);
+        Ok(self)
+    }
+
+    /// Add a header for request. If the header `key` is set, its value is replaced.
+    ///
+    /// Returns a `Self` for send request.
+    pub fn add_header(mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> Result<Self> {
+        let key = HeaderName::from_bytes(key.as_ref().as_bytes())
+            .map_err(|e| Error::InvalidRequest(format!("Cannot parse header: {e}")))?;
+        self.request.headers.insert(key, value.as_ref().parse()?);
+
         Ok(self)
     }
 
@@ -148,7 +275,53 @@ impl LogServiceClient {
         self
     }
 
-    /// Set a timeout for connect, read and write operations of a `Client`.
+    /// Set the source of current time to sign requests.
+    ///
+    /// Default is [`SystemClock`].
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+
+        self
+    }
+
+    /// Set the http client to send requests.
+    ///
+    /// The connection pool of the http client is shared by all requests and clones of
+    /// this client. Default is a `reqwest::Client` with default configs.
+    pub fn http_client(mut self, http_client: Client) -> Self {
+        self.transport = Arc::new(ReqwestTransport::new(http_client));
+
+        self
+    }
+
+    /// Set the transport to send signed requests.
+    ///
+    /// Default is [`ReqwestTransport`].
+    pub fn transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+
+        self
+    }
+
+    /// Set the policy to retry failed requests.
+    ///
+    /// Default is [`RetryPolicy::disabled`], which never retries.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+
+        self
+    }
+
+    /// Add a middleware to run around every attempt of request.
+    ///
+    /// See [`Middleware`] for the order of middlewares.
+    pub fn middleware(mut self, middleware: Arc<dyn Middleware>) -> Self {
+        self.middlewares.push(middleware);
+
+        self
+    }
+
+    /// Set a timeout for connect, read and write operations of a request.
     ///
     /// Default is no timeout.
     pub fn timeout(mut self, timeout: Duration) -> Self {
@@ -169,9 +342,87 @@ impl LogServiceClient {
         Ok(self.send().await?.text().await?)
     }
 
-    /// Send a request to service.
+    /// Send a request to service, retry the failed attempts following the retry policy.
     /// Return client Response.
-    pub async fn send(mut self) -> Result<Response> {
+    ///
+    /// If the failure is caused by clock skew, the offset of local clock is corrected with
+    /// the `Date` header of response, and the request is retried once.
+    pub async fn send(self) -> Result<Response> {
+        let offset = self.clock_skew.offset();
+        let result = self.send_with_retry().await;
+
+        // retry once with corrected time if the request failed by clock skew.
+        match result {
+            Err(e) if is_clock_skew_error(&e) && self.clock_skew.offset() != offset => {
+                self.send_with_retry().await
+            }
+            result => result,
+        }
+    }
+
+    /// Send a request, retry the failed attempts following the retry policy.
+    async fn send_with_retry(&self) -> Result<Response> {
+        self.retry_policy
+            .send(
+                &self.request.method,
+                || self.clone().send_once(),
+                |context, response| self.check_response(context, response),
+            )
+            .await
+    }
+
+    /// Get the current time, corrected with the detected clock skew.
+    fn now(&self) -> OffsetDateTime {
+        self.clock.now() + self.clock_skew.offset()
+    }
+
+    /// Build the context of request for middlewares.
+    fn context(&self) -> RequestContext {
+        RequestContext {
+            method: self.request.method.clone(),
+            endpoint: self.endpoint.clone(),
+            action: None,
+            uri: self.request.uri.clone(),
+            version: String::new(),
+            query: self.request.query.clone(),
+            headers: self.request.headers.clone(),
+        }
+    }
+
+    /// Apply the context of request changed by middlewares.
+    fn apply_context(&mut self, context: RequestContext) {
+        self.request.method = context.method;
+        self.endpoint = context.endpoint;
+        self.request.uri = context.uri;
+        self.request.query = context.query;
+        self.request.headers = context.headers;
+    }
+
+    /// Build, sign and send a single attempt of request.
+    /// Return the context of request and the response.
+    async fn send_once(mut self) -> Result<(RequestContext, Response)> {
+        // run middlewares before signing.
+        let mut context = self.context();
+        for middleware in self.middlewares.iter() {
+            middleware.before_sign(&mut context).await?;
+        }
+        self.apply_context(context.clone());
+
+        // pass the error to middlewares if the attempt fails before the response arrives.
+        let middlewares = self.middlewares.clone();
+        match self.sign_and_send(&context).await {
+            Ok(response) => Ok((context, response)),
+            Err(e) => {
+                for middleware in middlewares.iter().rev() {
+                    middleware.after_response(&context, Err(&e)).await;
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Sign and send the request changed by middlewares.
+    async fn sign_and_send(mut self, context: &RequestContext) -> Result<Response> {
         // check special header
         if !self.request.headers.contains_key("x-log-bodyrawsize") {
             self.request
@@ -188,6 +439,11 @@ impl LogServiceClient {
         for (k, v) in DEFAULT_HEADER.iter() {
             self.request.headers.insert(*k, v.parse()?);
         }
+        // log service only supports the `hmac-sha1` signature method.
+        self.request.headers.insert(
+            "x-log-signaturemethod",
+            HmacSha1Signer.name().to_lowercase().parse()?,
+        );
 
         // add host header.
         let mut prefix = "";
@@ -209,46 +465,71 @@ impl LogServiceClient {
         let format = format_description!(
             "[weekday repr:short], [day] [month repr:short] [year] [hour]:[minute]:[second] GMT"
         );
-        let ts = OffsetDateTime::now_utc()
+        let ts = self
+            .now()
             .format(&format)
             .map_err(|e| Error::InvalidRequest(format!("Invalid RFC 1123 Date: {}", e)))?;
         self.request.headers.insert("date", ts.parse()?);
 
+        // add security token header.
+        let credential = self.credential_provider.get_credentials().await?;
+        if let Some(security_token) = credential.security_token.as_ref() {
+            self.request
+                .headers
+                .insert("x-acs-security-token", security_token.parse()?);
+        }
+
         // compute `Authorization` field.
         // Authorization = "SLS <AccessKeyId>:<Signature>"
-        let authorization = format!("SLS {}:{}", self.access_key_id, self.signature()?);
+        let authorization = format!(
+            "SLS {}:{}",
+            credential.access_key_id,
+            self.signature(&credential)?
+        );
         self.request
             .headers
             .insert("Authorization", authorization.parse()?);
 
-        // build http client.
+        // build http request.
         let final_url = format!("{}{}{}", prefix, host, self.request.uri);
-        let mut http_client_builder = ClientBuilder::new();
-        if let Some(timeout) = self.request.timeout {
-            http_client_builder = http_client_builder.timeout(timeout);
-        }
-        let mut http_client = http_client_builder.build()?.request(
-            self.request
-                .method
-                .parse()
-                .map_err(|e| Error::InvalidRequest(format!("Invalid HTTP method: {}", e)))?,
+        let mut request = new_request(
+            &self.request.method,
             &final_url,
-        );
-
-        // set body.
-        if let Some(body) = self.request.body {
-            http_client = http_client.body(body);
+            &self.request.query,
+            self.request.headers,
+            self.request.body,
+        )?;
+
+        // run middlewares after signing.
+        for middleware in self.middlewares.iter() {
+            middleware.after_sign(context, &mut request).await?;
         }
 
-        // set query.
-        if !self.request.query.is_empty() {
-            http_client = http_client.query(&self.request.query);
+        // send request.
+        self.transport.execute(request, self.request.timeout).await
+    }
+
+    /// Check HTTP StatusCode of response, and pass the result to middlewares.
+    async fn check_response(
+        &self,
+        context: RequestContext,
+        response: Response,
+    ) -> Result<Response> {
+        let date = response.headers().get("date").cloned();
+        let result = Self::error_for_status(response).await;
+        if let Err(e) = result.as_ref() {
+            self.clock_skew
+                .detect(self.clock.as_ref(), date.as_ref(), e);
+        }
+        for middleware in self.middlewares.iter().rev() {
+            middleware.after_response(&context, result.as_ref()).await;
         }
 
-        // send request.
-        let response = http_client.headers(self.request.headers).send().await?;
+        result
+    }
 
-        // check HTTP StatusCode.
+    /// Turn a response with failed HTTP status into an error.
+    async fn error_for_status(response: Response) -> Result<Response> {
         if !response.status().is_success() {
             let result = response.json::<LogServiceError>().await?;
             return Err(Error::InvalidResponse {
@@ -258,7 +539,6 @@ impl LogServiceClient {
             });
         }
 
-        // return response.
         Ok(response)
     }
 
@@ -301,7 +581,7 @@ impl LogServiceClient {
     }
 
     /// Compute signature for request.
-    fn signature(&self) -> Result<String> {
+    fn signature(&self, credential: &Credential) -> Result<String> {
         // build body.
         let canonicalized_headers = self.canonicalized_headers();
         let canonicalized_resource = self.canonicalized_resource();
@@ -326,11 +606,7 @@ impl LogServiceClient {
         );
 
         // sign body.
-        let mut mac = HamcSha1::new_from_slice(self.access_key_secret.as_bytes())
-            .map_err(|e| Error::InvalidRequest(format!("Invalid HMAC-SHA1 secret key: {}", e)))?;
-        mac.update(body.as_bytes());
-        let result = mac.finalize();
-        let code = result.into_bytes();
+        let code = HmacSha1Signer.sign(&credential.access_key_secret, body.as_bytes())?;
 
         Ok(base64::encode(code))
     }
@@ -339,6 +615,7 @@ impl LogServiceClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::client::mock::MockTransport;
     use std::env;
 
     #[tokio::test]
@@ -370,4 +647,74 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn log_service_client_mock_test() -> Result<()> {
+        let transport = MockTransport::new().access_key("id", "secret").on_path(
+            "POST",
+            "/logstores/logstore/shards/lb",
+            200,
+            "{}",
+        );
+        let client = LogServiceClient::new("id", "secret", "https://cn-hangzhou.log.aliyuncs.com")
+            .transport(Arc::new(transport));
+
+        let response = client
+            .clone()
+            .post("/logstores/logstore/shards/lb")
+            .project("project")
+            .query(vec![("key".to_string(), "value".to_string())])
+            .body("log")?
+            .text()
+            .await?;
+        assert_eq!(response, "{}");
+
+        // binary body, such as protobuf log group.
+        let response = client
+            .post("/logstores/logstore/shards/lb")
+            .project("project")
+            .header([(
+                "content-type".to_string(),
+                "application/x-protobuf".to_string(),
+            )])?
+            .binary_body(vec![0x0a, 0x00, 0xff])?
+            .text()
+            .await?;
+        assert_eq!(response, "{}");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn log_service_client_merge_test() -> Result<()> {
+        let transport = MockTransport::new().access_key("id", "secret").on_path(
+            "POST",
+            "/logstores/logstore/shards/lb",
+            200,
+            "{}",
+        );
+        LogServiceClient::new("id", "secret", "https://cn-hangzhou.log.aliyuncs.com")
+            .transport(Arc::new(transport.clone()))
+            .post("/logstores/logstore/shards/lb")
+            .project("project")
+            .add_query("key", "value")
+            .query_opt("offset", None::<String>)
+            .query_opt("line", Some("100"))
+            .add_query("key", "other")
+            .body("log")?
+            .header([("x-log-compresstype".to_string(), "lz4".to_string())])?
+            .add_header("x-log-bodyrawsize", "3")?
+            .text()
+            .await?;
+
+        let requests = transport.requests();
+        assert_eq!(requests[0].url.query(), Some("key=other&line=100"));
+        let headers = &requests[0].headers;
+        assert_eq!(headers["content-length"], "3");
+        assert_eq!(headers["content-md5"], "DC1D71BBB5C4D2A5E936DB79EF10C19F");
+        assert_eq!(headers["x-log-compresstype"], "lz4");
+        assert_eq!(headers["x-log-bodyrawsize"], "3");
+
+        Ok(())
+    }
 }
@@ -0,0 +1,218 @@
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use reqwest::{header::HeaderMap, Response};
+
+use crate::client::{
+    error::{Error, Result},
+    transport::HttpRequest,
+};
+
+/// The context of an api request passed to middlewares.
+#[derive(Clone, Debug, Default)]
+pub struct RequestContext {
+    /// The HTTP method.
+    pub method: String,
+    /// The api endpoint.
+    pub endpoint: String,
+    /// The api name, `None` for the ROA api without action and the SLS api.
+    pub action: Option<String>,
+    /// The uri of ROA and SLS api, empty for RPC api.
+    pub uri: String,
+    /// The api version, empty for SLS api.
+    pub version: String,
+    /// The queries of request.
+    pub query: Vec<(String, String)>,
+    /// The headers of request.
+    pub headers: HeaderMap,
+}
+
+/// The middleware to inject behaviors into every attempt of request.
+///
+/// Middlewares run in the order they are added before and after signing, and in the
+/// reverse order after the response arrives. All hooks do nothing by default.
+#[async_trait]
+pub trait Middleware: Debug + Send + Sync {
+    /// Called before the request is signed.
+    ///
+    /// Changes of the context are applied to the request. Returns an error to abort the request.
+    async fn before_sign(&self, _context: &mut RequestContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called after the request is signed, before it is sent.
+    ///
+    /// Changes of the signed parts of request make the signature invalid.
+    /// Returns an error to abort the request.
+    async fn after_sign(
+        &self,
+        _context: &RequestContext,
+        _request: &mut HttpRequest,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called after the response arrives, with the error parsed from the failed response.
+    ///
+    /// Also called with the error if the attempt fails after [`before_sign`](Self::before_sign)
+    /// but before the response arrives, such as transport errors, timeouts and open circuit.
+    async fn after_response(&self, _context: &RequestContext, _result: Result<&Response, &Error>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::client::{
+        circuit_breaker::CircuitBreaker,
+        log_service::LogServiceClient,
+        mock::MockTransport,
+        roa::ROAClient,
+        rpc::RPClient,
+        transport::{HttpResponse, HttpTransport},
+    };
+
+    #[derive(Debug, Default)]
+    struct AuditMiddleware {
+        logs: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl Middleware for AuditMiddleware {
+        async fn before_sign(&self, context: &mut RequestContext) -> Result<()> {
+            context.headers.insert("x-audit-id", "audit".parse()?);
+            context
+                .query
+                .push(("RegionId".to_string(), "cn-hangzhou".to_string()));
+
+            Ok(())
+        }
+
+        async fn after_sign(
+            &self,
+            context: &RequestContext,
+            request: &mut HttpRequest,
+        ) -> Result<()> {
+            self.logs.lock().unwrap().push(format!(
+                "{} {}",
+                context.action.as_deref().unwrap_or_default(),
+                request
+                    .uri()
+                    .query()
+                    .unwrap_or_default()
+                    .contains("Signature=")
+            ));
+
+            Ok(())
+        }
+
+        async fn after_response(
+            &self,
+            _context: &RequestContext,
+            result: Result<&Response, &Error>,
+        ) {
+            match result {
+                Err(Error::InvalidResponse { error_code, .. }) => {
+                    self.logs.lock().unwrap().push(error_code.clone())
+                }
+                Err(e) => self.logs.lock().unwrap().push(e.to_string()),
+                Ok(_) => {}
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn middleware_test() -> Result<()> {
+        let transport = MockTransport::new().access_key("id", "secret").on_action(
+            "DescribeInstances",
+            400,
+            r#"{"RequestId":"request-id","Code":"InvalidParameter","Message":"invalid"}"#,
+        );
+
+        let middleware = Arc::new(AuditMiddleware::default());
+        let result = RPClient::new("id", "secret", "https://ecs.aliyuncs.com/")
+            .transport(Arc::new(transport.clone()))
+            .middleware(middleware.clone())
+            .version("2014-05-26")
+            .get("DescribeInstances")
+            .text()
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(
+            *middleware.logs.lock().unwrap(),
+            vec!["DescribeInstances true", "InvalidParameter"]
+        );
+
+        // the middleware changes are signed and sent.
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].headers["x-audit-id"], "audit");
+        assert!(requests[0]
+            .url
+            .query_pairs()
+            .any(|(k, v)| k == "RegionId" && v == "cn-hangzhou"));
+
+        Ok(())
+    }
+
+    /// The transport which fails to connect.
+    #[derive(Debug)]
+    struct FailingTransport;
+
+    #[async_trait]
+    impl HttpTransport for FailingTransport {
+        async fn send(&self, _request: HttpRequest) -> Result<HttpResponse> {
+            Err(Error::InvalidRequest("connection refused".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn middleware_transport_error_test() -> Result<()> {
+        let middleware = Arc::new(AuditMiddleware::default());
+        let rpc_client = RPClient::new("id", "secret", "https://ecs.aliyuncs.com/")
+            .transport(Arc::new(FailingTransport))
+            .middleware(middleware.clone())
+            .circuit_breaker(CircuitBreaker::new().failure_threshold(1))
+            .version("2014-05-26");
+        for _ in 0..2 {
+            let result = rpc_client.clone().get("DescribeInstances").text().await;
+            assert!(result.is_err());
+        }
+        let result = ROAClient::new("id", "secret", "https://cs.aliyuncs.com")
+            .transport(Arc::new(FailingTransport))
+            .middleware(middleware.clone())
+            .version("2015-12-15")
+            .get("/clusters")
+            .text()
+            .await;
+        assert!(result.is_err());
+        let result = LogServiceClient::new("id", "secret", "https://cn-hangzhou.log.aliyuncs.com")
+            .transport(Arc::new(FailingTransport))
+            .middleware(middleware.clone())
+            .get("/logstores")
+            .project("project")
+            .text()
+            .await;
+        assert!(result.is_err());
+
+        // the errors before the response arrives are passed to middlewares.
+        let transport_error = "Request error: connection refused".to_string();
+        assert_eq!(
+            *middleware.logs.lock().unwrap(),
+            vec![
+                "DescribeInstances true".to_string(),
+                transport_error.clone(),
+                "DescribeInstances true".to_string(),
+                "Circuit breaker is open for endpoint: ecs.aliyuncs.com".to_string(),
+                " false".to_string(),
+                transport_error.clone(),
+                " false".to_string(),
+                transport_error,
+            ]
+        );
+
+        Ok(())
+    }
+}
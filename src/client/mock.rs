@@ -0,0 +1,900 @@
+//! Mock service of aliyun api for offline tests.
+//!
+//! Enable the `test-util` feature to use it.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use hmac::{digest::KeyInit, Hmac, Mac};
+use reqwest::{header::HeaderMap, StatusCode};
+use serde_json::json;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use sm3::Sm3;
+use url::{form_urlencoded, Url};
+
+use crate::client::{
+    error::Result,
+    transport::{request_url, HttpRequest, HttpResponse, HttpTransport},
+};
+
+/// The error to reply for a request which fails the verification.
+struct MockError {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+}
+
+impl MockError {
+    fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        MockError {
+            status,
+            code,
+            message: message.into(),
+        }
+    }
+
+    fn signature_mismatch() -> Self {
+        Self::new(
+            StatusCode::BAD_REQUEST,
+            "SignatureDoesNotMatch",
+            "Specified signature is not matched with our calculation.",
+        )
+    }
+
+    fn incomplete_signature(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "IncompleteSignature", message)
+    }
+}
+
+/// The signature method of request, implemented apart from the signers of clients, so the
+/// verification does not share the mistakes of clients.
+#[derive(Clone, Copy, Debug)]
+enum SignatureMethod {
+    HmacSha1,
+    HmacSha256,
+    HmacSm3,
+    #[cfg(feature = "rsa-signer")]
+    RsaSha256,
+}
+
+impl SignatureMethod {
+    /// Parse the name of signature method, such as `HMAC-SHA1`.
+    fn parse(name: &str) -> Result<Self, MockError> {
+        match name.to_uppercase().as_str() {
+            "HMAC-SHA1" => Ok(SignatureMethod::HmacSha1),
+            "HMAC-SHA256" => Ok(SignatureMethod::HmacSha256),
+            "HMAC-SM3" => Ok(SignatureMethod::HmacSm3),
+            #[cfg(feature = "rsa-signer")]
+            "RSA-SHA256" => Ok(SignatureMethod::RsaSha256),
+            _ => Err(MockError::new(
+                StatusCode::BAD_REQUEST,
+                "InvalidSignatureMethod",
+                format!("Unsupported signature method: {name}"),
+            )),
+        }
+    }
+
+    /// Sign data with the secret.
+    fn sign(&self, secret: &str, data: &[u8]) -> Result<Vec<u8>, MockError> {
+        match self {
+            SignatureMethod::HmacSha1 => Ok(hmac::<Hmac<Sha1>>(secret, data)),
+            SignatureMethod::HmacSha256 => Ok(hmac::<Hmac<Sha256>>(secret, data)),
+            SignatureMethod::HmacSm3 => Ok(hmac::<Hmac<Sm3>>(secret, data)),
+            #[cfg(feature = "rsa-signer")]
+            SignatureMethod::RsaSha256 => {
+                use rsa::{
+                    pkcs1::DecodeRsaPrivateKey,
+                    pkcs1v15::SigningKey,
+                    pkcs8::DecodePrivateKey,
+                    signature::{SignatureEncoding, Signer},
+                    RsaPrivateKey,
+                };
+
+                let private_key = RsaPrivateKey::from_pkcs8_pem(secret)
+                    .or_else(|_| RsaPrivateKey::from_pkcs1_pem(secret))
+                    .map_err(|_| MockError::signature_mismatch())?;
+
+                Ok(SigningKey::<Sha256>::new(private_key).sign(data).to_vec())
+            }
+        }
+    }
+
+    /// Compute the lowercase hex digest of V3 signature.
+    fn hex_hash(&self, data: &[u8]) -> Result<String, MockError> {
+        let digest = match self {
+            SignatureMethod::HmacSha1 => {
+                return Err(MockError::new(
+                    StatusCode::BAD_REQUEST,
+                    "InvalidSignatureMethod",
+                    "Unsupported signature method of V3 signature: HMAC-SHA1",
+                ))
+            }
+            SignatureMethod::HmacSm3 => Sm3::digest(data).to_vec(),
+            _ => Sha256::digest(data).to_vec(),
+        };
+
+        Ok(base16ct::lower::encode_string(&digest))
+    }
+
+    /// The header of hashed payload in V3 signature.
+    fn content_hash_header(&self) -> &'static str {
+        match self {
+            SignatureMethod::HmacSm3 => "x-acs-content-sm3",
+            _ => "x-acs-content-sha256",
+        }
+    }
+}
+
+/// Compute the HMAC of data with the secret.
+fn hmac<M: Mac + KeyInit>(secret: &str, data: &[u8]) -> Vec<u8> {
+    let mut mac = <M as Mac>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key");
+    mac.update(data);
+
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// The canned response of matched requests.
+#[derive(Clone, Debug)]
+struct Route {
+    /// The api name of RPC or ROA request to match.
+    action: Option<String>,
+    /// The HTTP method and path of ROA or SLS request to match.
+    path: Option<(String, String)>,
+    status: StatusCode,
+    body: String,
+}
+
+/// A request received by [`MockTransport`].
+#[derive(Clone, Debug)]
+pub struct MockRequest {
+    /// The HTTP method.
+    pub method: String,
+    /// The url with query.
+    pub url: Url,
+    /// The headers.
+    pub headers: HeaderMap,
+    /// The body, `None` for streaming body.
+    pub body: Option<Bytes>,
+}
+
+/// A mock transport which serves aliyun api locally.
+///
+/// It validates the signatures of RPC, ROA and SLS style requests with the registered
+/// access keys, like the real service does, then replies the canned response of the first
+/// matched route. The failed requests get the errors in the format of aliyun api, such as
+/// `SignatureDoesNotMatch` and `InvalidAction.NotFound`.
+///
+/// The requests without signature are rejected with `IncompleteSignature`, except the RPC
+/// requests of the actions registered by [`MockTransport::anonymous_action`], and the
+/// requests to the hosts registered by [`MockTransport::unsigned_host`]. All the received
+/// requests are kept, see [`MockTransport::requests`].
+#[derive(Clone, Debug, Default)]
+pub struct MockTransport {
+    /// The secrets by access key id.
+    access_keys: HashMap<String, String>,
+    /// The RPC actions which accept requests without signature.
+    anonymous_actions: HashSet<String>,
+    /// The hosts which accept requests without signature.
+    unsigned_hosts: HashSet<String>,
+    /// The canned responses.
+    routes: Vec<Route>,
+    /// The received requests, shared by clones.
+    requests: Arc<Mutex<Vec<MockRequest>>>,
+}
+
+impl MockTransport {
+    /// Create a mock transport without access keys and routes.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Register an access key to verify signatures.
+    pub fn access_key(
+        mut self,
+        access_key_id: impl Into<String>,
+        access_key_secret: impl Into<String>,
+    ) -> Self {
+        self.access_keys
+            .insert(access_key_id.into(), access_key_secret.into());
+
+        self
+    }
+
+    /// Accept the RPC requests of the `action` without signature, like the APIs which allow
+    /// anonymous access, such as STS `AssumeRoleWithOIDC`.
+    pub fn anonymous_action(mut self, action: impl Into<String>) -> Self {
+        self.anonymous_actions.insert(action.into());
+
+        self
+    }
+
+    /// Accept the requests to the `host` without signature, like the services which are not
+    /// aliyun api, such as the ECS metadata service `100.100.100.200`.
+    pub fn unsigned_host(mut self, host: impl Into<String>) -> Self {
+        self.unsigned_hosts.insert(host.into());
+
+        self
+    }
+
+    /// Reply the RPC or ROA requests of the `action` with `status` and `body`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `status` is not a valid HTTP status code.
+    pub fn on_action(
+        mut self,
+        action: impl Into<String>,
+        status: u16,
+        body: impl Into<String>,
+    ) -> Self {
+        self.routes.push(Route {
+            action: Some(action.into()),
+            path: None,
+            status: status_code(status),
+            body: body.into(),
+        });
+
+        self
+    }
+
+    /// Reply the ROA or SLS requests of the `method` and the decoded `path` with `status` and `body`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `status` is not a valid HTTP status code.
+    pub fn on_path(
+        mut self,
+        method: impl Into<String>,
+        path: impl Into<String>,
+        status: u16,
+        body: impl Into<String>,
+    ) -> Self {
+        self.routes.push(Route {
+            action: None,
+            path: Some((method.into().to_uppercase(), path.into())),
+            status: status_code(status),
+            body: body.into(),
+        });
+
+        self
+    }
+
+    /// Get the requests received by the transport and its clones, in the received order.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Get the secret of the access key.
+    fn secret(&self, access_key_id: &str) -> Result<&str, MockError> {
+        self.access_keys
+            .get(access_key_id)
+            .map(String::as_str)
+            .ok_or_else(|| {
+                MockError::new(
+                    StatusCode::NOT_FOUND,
+                    "InvalidAccessKeyId.NotFound",
+                    format!("Specified access key is not found: {access_key_id}"),
+                )
+            })
+    }
+
+    /// Verify the signature of request.
+    fn verify(&self, request: &HttpRequest, url: &Url) -> Result<(), MockError> {
+        if url
+            .host_str()
+            .is_some_and(|host| self.unsigned_hosts.contains(host))
+        {
+            return Ok(());
+        }
+
+        let authorization = header(request.headers(), "authorization");
+        if let Some(authorization) = authorization.strip_prefix("ACS3-") {
+            self.verify_v3(request, url, authorization)
+        } else if let Some(authorization) = authorization.strip_prefix("acs ") {
+            self.verify_v1(request, url, authorization, "x-acs-signature-method", false)
+        } else if let Some(authorization) = authorization.strip_prefix("SLS ") {
+            self.verify_v1(request, url, authorization, "x-log-signaturemethod", true)
+        } else if rpc_params(request, url)
+            .iter()
+            .any(|(k, _)| k == "Signature")
+        {
+            self.verify_rpc_v1(request, url)
+        } else if rpc_params(request, url)
+            .iter()
+            .any(|(k, _)| k == "AccessKeyId")
+        {
+            Err(MockError::incomplete_signature("Missing Signature"))
+        } else if rpc_action(request, url).is_some_and(|a| self.anonymous_actions.contains(&a)) {
+            Ok(())
+        } else {
+            Err(MockError::incomplete_signature("Missing Authorization"))
+        }
+    }
+
+    /// Verify the V1 signature of RPC request.
+    fn verify_rpc_v1(&self, request: &HttpRequest, url: &Url) -> Result<(), MockError> {
+        let mut params = rpc_params(request, url);
+        let signature = take_param(&mut params, "Signature").unwrap_or_default();
+        let param = |key: &str| {
+            params
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.clone())
+                .ok_or_else(|| MockError::incomplete_signature(format!("Missing {key}")))
+        };
+        let secret = self.secret(&param("AccessKeyId")?)?;
+        let signature_method = SignatureMethod::parse(&param("SignatureMethod")?)?;
+
+        params.sort_by(|a, b| a.0.cmp(&b.0));
+        let params: Vec<String> = params
+            .iter()
+            .map(|(k, v)| format!("{}={}", rfc3986_encode(k, b""), rfc3986_encode(v, b"")))
+            .collect();
+        let string_to_sign = format!(
+            "{}&{}&{}",
+            request.method(),
+            rfc3986_encode("/", b""),
+            rfc3986_encode(&params.join("&"), b"")
+        );
+        let expected = signature_method.sign(&format!("{secret}&"), string_to_sign.as_bytes())?;
+
+        if base64::encode(expected) != signature {
+            return Err(MockError::signature_mismatch());
+        }
+
+        Ok(())
+    }
+
+    /// Verify the V1 signature of ROA request, or the signature of SLS request.
+    fn verify_v1(
+        &self,
+        request: &HttpRequest,
+        url: &Url,
+        authorization: &str,
+        signature_method_header: &str,
+        is_sls: bool,
+    ) -> Result<(), MockError> {
+        let (access_key_id, signature) = authorization
+            .split_once(':')
+            .ok_or_else(|| MockError::incomplete_signature("Invalid Authorization"))?;
+        let secret = self.secret(access_key_id)?;
+        let headers = request.headers();
+        let signature_method = SignatureMethod::parse(header(headers, signature_method_header))?;
+
+        // canonicalized headers.
+        let mut canonicalized_headers: Vec<(&str, &str)> = headers
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.to_str().unwrap_or_default()))
+            .filter(|(k, _)| k.starts_with("x-acs-") || is_sls && k.starts_with("x-log-"))
+            .collect();
+        canonicalized_headers.sort_by(|a, b| a.0.cmp(b.0));
+        let canonicalized_headers: Vec<String> = canonicalized_headers
+            .iter()
+            .map(|(k, v)| format!("{k}:{v}"))
+            .collect();
+
+        // canonicalized resource.
+        let mut params: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+        params.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut canonicalized_resource = percent_decode(url.path());
+        if !params.is_empty() {
+            let params: Vec<String> = params.iter().map(|(k, v)| format!("{k}={v}")).collect();
+            canonicalized_resource = format!("{}?{}", canonicalized_resource, params.join("&"));
+        }
+
+        let mut string_to_sign = vec![request.method().as_str()];
+        if !is_sls {
+            string_to_sign.push(header(headers, "accept"));
+        }
+        string_to_sign.extend([
+            header(headers, "content-md5"),
+            header(headers, "content-type"),
+            header(headers, "date"),
+        ]);
+        let canonicalized_headers = canonicalized_headers.join("\n");
+        string_to_sign.extend([canonicalized_headers.as_str(), &canonicalized_resource]);
+        let expected = signature_method.sign(secret, string_to_sign.join("\n").as_bytes())?;
+
+        if base64::encode(expected) != signature {
+            return Err(MockError::signature_mismatch());
+        }
+
+        Ok(())
+    }
+
+    /// Verify the V3 signature of RPC or ROA request.
+    fn verify_v3(
+        &self,
+        request: &HttpRequest,
+        url: &Url,
+        authorization: &str,
+    ) -> Result<(), MockError> {
+        let (algorithm, fields) = authorization
+            .split_once(' ')
+            .ok_or_else(|| MockError::incomplete_signature("Invalid Authorization"))?;
+        let fields: HashMap<&str, &str> = fields
+            .split(',')
+            .filter_map(|field| field.split_once('='))
+            .collect();
+        let field = |key: &str| {
+            fields
+                .get(key)
+                .copied()
+                .ok_or_else(|| MockError::incomplete_signature(format!("Missing {key}")))
+        };
+        let secret = self.secret(field("Credential")?)?;
+        let signature_method = SignatureMethod::parse(algorithm)?;
+        let content_hash_header = signature_method.content_hash_header();
+
+        // the hashed payload must match the body, streaming body can not be hashed.
+        let body = request.body().as_bytes().ok_or_else(|| {
+            MockError::incomplete_signature("Streaming body is not supported by V3 signature")
+        })?;
+        let content_hash = signature_method.hex_hash(body)?;
+        if header(request.headers(), content_hash_header) != content_hash {
+            return Err(MockError::new(
+                StatusCode::BAD_REQUEST,
+                "ContentHashMismatch",
+                "The hashed payload is not matched with the body.",
+            ));
+        }
+
+        // the signed headers are sorted lowercase names, including host and hashed payload.
+        let signed_headers: Vec<&str> = field("SignedHeaders")?.split(';').collect();
+        if !signed_headers.windows(2).all(|names| names[0] < names[1])
+            || signed_headers
+                .iter()
+                .any(|name| *name != name.to_lowercase())
+            || !signed_headers.contains(&"host")
+            || !signed_headers.contains(&content_hash_header)
+        {
+            return Err(MockError::incomplete_signature("Invalid SignedHeaders"));
+        }
+        let mut canonical_headers = String::new();
+        for name in &signed_headers {
+            let value = request
+                .headers()
+                .get(*name)
+                .and_then(|value| value.to_str().ok())
+                .ok_or_else(|| {
+                    MockError::incomplete_signature(format!("Missing signed header {name}"))
+                })?;
+            canonical_headers.push_str(&format!("{name}:{}\n", value.trim()));
+        }
+
+        let mut query: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(k, v)| (rfc3986_encode(&k, b""), rfc3986_encode(&v, b"")))
+            .collect();
+        query.sort();
+        let query: Vec<String> = query.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        let canonical_request = [
+            request.method().as_str(),
+            &rfc3986_encode(&percent_decode(url.path()), b"/"),
+            &query.join("&"),
+            &canonical_headers,
+            &signed_headers.join(";"),
+            &content_hash,
+        ]
+        .join("\n");
+        let string_to_sign = format!(
+            "ACS3-{algorithm}\n{}",
+            signature_method.hex_hash(canonical_request.as_bytes())?
+        );
+        let expected = signature_method.sign(secret, string_to_sign.as_bytes())?;
+
+        if base16ct::lower::encode_string(&expected) != field("Signature")? {
+            return Err(MockError::signature_mismatch());
+        }
+
+        Ok(())
+    }
+
+    /// Find the route of request.
+    fn route(&self, request: &HttpRequest, url: &Url) -> Option<&Route> {
+        let action = rpc_action(request, url)
+            .unwrap_or_else(|| header(request.headers(), "x-acs-action").to_string());
+        let method = request.method().as_str();
+        let path = percent_decode(url.path());
+
+        self.routes.iter().find(|route| {
+            route.action.as_deref() == Some(action.as_str())
+                || route
+                    .path
+                    .as_ref()
+                    .is_some_and(|(m, p)| m == method && *p == path)
+        })
+    }
+}
+
+#[async_trait]
+impl HttpTransport for MockTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse> {
+        let url = request_url(request.uri())?;
+        self.requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(MockRequest {
+                method: request.method().to_string(),
+                url: url.clone(),
+                headers: request.headers().clone(),
+                body: request.body().as_bytes().map(Bytes::copy_from_slice),
+            });
+
+        if let Err(e) = self.verify(&request, &url) {
+            return Ok(error_response(e));
+        }
+
+        let Some(route) = self.route(&request, &url) else {
+            return Ok(error_response(MockError::new(
+                StatusCode::NOT_FOUND,
+                "InvalidAction.NotFound",
+                format!("No mock response for {} {}", request.method(), url),
+            )));
+        };
+
+        Ok(response(route.status, route.body.clone()))
+    }
+}
+
+/// Get the params of RPC request from the query and the form body.
+fn rpc_params(request: &HttpRequest, url: &Url) -> Vec<(String, String)> {
+    let mut params: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    if header(request.headers(), "content-type") == "application/x-www-form-urlencoded" {
+        let body = request.body().as_bytes().unwrap_or_default();
+        params.extend(form_urlencoded::parse(body).into_owned());
+    }
+
+    params
+}
+
+/// Get the action of RPC request, from the `Action` param of V1 signature, or the
+/// `x-acs-action` header of V3 signature which is sent to the root path.
+fn rpc_action(request: &HttpRequest, url: &Url) -> Option<String> {
+    rpc_params(request, url)
+        .into_iter()
+        .find(|(k, _)| k == "Action")
+        .map(|(_, v)| v)
+        .or_else(|| {
+            let action = header(request.headers(), "x-acs-action");
+            (url.path() == "/" && !action.is_empty()).then(|| action.to_string())
+        })
+}
+
+/// Encode the string following RFC3986, the unreserved characters and `keep` are not encoded.
+fn rfc3986_encode(s: &str, keep: &[u8]) -> String {
+    s.bytes()
+        .map(|b| {
+            if b.is_ascii_alphanumeric() || b"-_.~".contains(&b) || keep.contains(&b) {
+                char::from(b).to_string()
+            } else {
+                format!("%{b:02X}")
+            }
+        })
+        .collect()
+}
+
+/// Decode the percent-encoded string, such as the path of url.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = s
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match byte {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Get the value of header, or empty string if it is missing.
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> &'a str {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+}
+
+/// Remove the param from params, return its value.
+fn take_param(params: &mut Vec<(String, String)>, key: &str) -> Option<String> {
+    let index = params.iter().position(|(k, _)| k == key)?;
+    Some(params.remove(index).1)
+}
+
+/// Parse the status code of canned response.
+fn status_code(status: u16) -> StatusCode {
+    StatusCode::from_u16(status)
+        .unwrap_or_else(|_| panic!("invalid HTTP status code of mock response: {status}"))
+}
+
+/// Build a JSON response.
+fn response(status: StatusCode, body: String) -> HttpResponse {
+    http::Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(body.into())
+        .expect("the status and header are valid")
+}
+
+/// Build an error response which can be parsed by the RPC, ROA and SLS clients.
+fn error_response(error: MockError) -> HttpResponse {
+    let body = json!({
+        "RequestId": "mock-request-id",
+        "Code": error.code,
+        "Message": error.message,
+        "errorCode": error.code,
+        "errorMessage": error.message,
+    });
+
+    response(error.status, body.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{
+        error::Error, log_service::LogServiceClient, roa::ROAClient, rpc::RPClient,
+        signature::SignatureVersion, transport::Body,
+    };
+
+    fn is_signature_mismatch(result: Result<String>) -> bool {
+        matches!(
+            result,
+            Err(Error::InvalidResponse { error_code, .. }) if error_code == "SignatureDoesNotMatch"
+        )
+    }
+
+    fn mock_transport() -> Arc<MockTransport> {
+        Arc::new(MockTransport::new().access_key("id", "secret").on_action(
+            "DescribeRegions",
+            200,
+            r#"{"Regions":{}}"#,
+        ))
+    }
+
+    #[tokio::test]
+    async fn mock_transport_rpc_test() -> Result<()> {
+        for signature_version in [SignatureVersion::V1, SignatureVersion::V3] {
+            let response = RPClient::new("id", "secret", "https://ecs.aliyuncs.com/")
+                .transport(mock_transport())
+                .signature_version(signature_version)
+                .version("2014-05-26")
+                .get("DescribeRegions")
+                .query([("RegionId", "cn-hangzhou")])
+                .text()
+                .await?;
+            assert_eq!(response, r#"{"Regions":{}}"#);
+        }
+
+        // invalid secret.
+        for signature_version in [SignatureVersion::V1, SignatureVersion::V3] {
+            let result = RPClient::new("id", "invalid", "https://ecs.aliyuncs.com/")
+                .transport(mock_transport())
+                .signature_version(signature_version)
+                .version("2014-05-26")
+                .get("DescribeRegions")
+                .text()
+                .await;
+            assert!(is_signature_mismatch(result));
+        }
+
+        // no mock response.
+        let result = RPClient::new("id", "secret", "https://ecs.aliyuncs.com/")
+            .transport(mock_transport())
+            .version("2014-05-26")
+            .get("DescribeInstances")
+            .text()
+            .await;
+        assert!(matches!(
+            result,
+            Err(Error::InvalidResponse { error_code, .. }) if error_code == "InvalidAction.NotFound"
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn mock_transport_roa_test() -> Result<()> {
+        let transport = Arc::new(MockTransport::new().access_key("id", "secret").on_path(
+            "GET",
+            "/clusters/a b",
+            200,
+            "{}",
+        ));
+        for signature_version in [SignatureVersion::V1, SignatureVersion::V3] {
+            for (secret, is_valid) in [("secret", true), ("invalid", false)] {
+                let result = ROAClient::new("id", secret, "https://cs.aliyuncs.com")
+                    .transport(transport.clone())
+                    .signature_version(signature_version)
+                    .version("2015-12-15")
+                    .action("DescribeClusterDetail")
+                    .get("/clusters/a b")
+                    .query([("RegionId", "cn-hangzhou")])
+                    .text()
+                    .await;
+                if is_valid {
+                    assert_eq!(result?, "{}");
+                } else {
+                    assert!(is_signature_mismatch(result));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn mock_transport_log_service_test() -> Result<()> {
+        let transport = Arc::new(MockTransport::new().access_key("id", "secret").on_path(
+            "GET",
+            "/logstores",
+            200,
+            "{}",
+        ));
+        for (secret, is_valid) in [("secret", true), ("invalid", false)] {
+            let result =
+                LogServiceClient::new("id", secret, "https://cn-hangzhou.log.aliyuncs.com")
+                    .transport(transport.clone())
+                    .get("/logstores")
+                    .project("project")
+                    .add_query("offset", "0")
+                    .text()
+                    .await;
+            if is_valid {
+                assert_eq!(result?, "{}");
+            } else {
+                assert!(is_signature_mismatch(result));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn is_incomplete_signature(result: Result<String>) -> bool {
+        matches!(
+            result,
+            Err(Error::InvalidResponse { error_code, .. }) if error_code == "IncompleteSignature"
+        )
+    }
+
+    #[tokio::test]
+    async fn mock_transport_unsigned_test() -> Result<()> {
+        let transport = Arc::new(
+            MockTransport::new()
+                .anonymous_action("AssumeRoleWithOIDC")
+                .on_action("AssumeRoleWithOIDC", 200, "{}")
+                .on_action("DescribeRegions", 200, "{}")
+                .on_path("GET", "/clusters", 200, "{}")
+                .unsigned_host("100.100.100.200")
+                .on_path("GET", "/latest/meta-data/", 200, "{}"),
+        );
+        let rpc = |action: &str, signature_version| {
+            RPClient::new("", "", "https://sts.aliyuncs.com/")
+                .transport(transport.clone())
+                .signature_version(signature_version)
+                .version("2015-04-01")
+                .post(action)
+                .anonymous()
+                .text()
+        };
+
+        // only the anonymous actions accept unsigned requests.
+        for signature_version in [SignatureVersion::V1, SignatureVersion::V3] {
+            assert_eq!(rpc("AssumeRoleWithOIDC", signature_version).await?, "{}");
+            assert!(is_incomplete_signature(
+                rpc("DescribeRegions", signature_version).await
+            ));
+        }
+
+        // unsigned ROA or SLS request.
+        let request = |uri: &str| {
+            http::Request::builder()
+                .method("GET")
+                .uri(uri)
+                .body(Body::default())
+                .unwrap()
+        };
+        let response = transport
+            .send(request("https://cs.aliyuncs.com/clusters"))
+            .await?;
+        assert_eq!(response.status(), 400);
+        assert!(String::from_utf8_lossy(response.body()).contains("IncompleteSignature"));
+
+        // the unsigned hosts accept requests without signature.
+        let response = transport
+            .send(request("http://100.100.100.200/latest/meta-data/"))
+            .await?;
+        assert_eq!(response.status(), 200);
+
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid HTTP status code")]
+    fn mock_transport_invalid_status_test() {
+        MockTransport::new().on_path("GET", "/clusters", 1000, "{}");
+    }
+
+    /// The example of V3 signature published in
+    /// <https://help.aliyun.com/zh/sdk/product-overview/v3-request-structure-and-signature>.
+    #[tokio::test]
+    async fn mock_transport_v3_published_example_test() -> Result<()> {
+        let transport = MockTransport::new()
+            .access_key("YourAccessKeyId", "YourAccessKeySecret")
+            .on_action("RunInstances", 200, "{}");
+        let request = |signature: &str| {
+            http::Request::builder()
+                .method("POST")
+                .uri(
+                    "https://ecs.cn-shanghai.aliyuncs.com/\
+                     ?ImageId=win2019_1809_x64_dtc_zh-cn_40G_alibase_20230811.vhd\
+                     &RegionId=cn-shanghai",
+                )
+                .header("host", "ecs.cn-shanghai.aliyuncs.com")
+                .header("x-acs-action", "RunInstances")
+                .header(
+                    "x-acs-content-sha256",
+                    "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+                )
+                .header("x-acs-date", "2023-10-26T10:22:32Z")
+                .header("x-acs-signature-nonce", "3156853299f313e23d1673dc12e1703d")
+                .header("x-acs-version", "2014-05-26")
+                .header(
+                    "authorization",
+                    format!(
+                        "ACS3-HMAC-SHA256 Credential=YourAccessKeyId,\
+                         SignedHeaders=host;x-acs-action;x-acs-content-sha256;x-acs-date;\
+                         x-acs-signature-nonce;x-acs-version,\
+                         Signature={signature}"
+                    ),
+                )
+                .body(Body::default())
+                .unwrap()
+        };
+
+        let response = transport
+            .send(request(
+                "06563a9e1b43f5dfe96b81484da74bceab24a1d853912eee15083a6f0f3283c0",
+            ))
+            .await?;
+        assert_eq!(response.status(), 200);
+        let response = transport
+            .send(request(
+                "06563a9e1b43f5dfe96b81484da74bceab24a1d853912eee15083a6f0f3283c1",
+            ))
+            .await?;
+        assert_eq!(response.status(), 400);
+
+        // the hashed payload must be computed from the body.
+        let mut unsigned_payload =
+            request("06563a9e1b43f5dfe96b81484da74bceab24a1d853912eee15083a6f0f3283c0");
+        unsigned_payload.headers_mut().insert(
+            "x-acs-content-sha256",
+            http::HeaderValue::from_static("UNSIGNED-PAYLOAD"),
+        );
+        let response = transport.send(unsigned_payload).await?;
+        assert_eq!(response.status(), 400);
+
+        Ok(())
+    }
+}
@@ -1,4 +1,36 @@
+pub(crate) mod body;
+#[cfg(any(test, feature = "test-util"))]
+pub mod cassette;
+pub mod circuit_breaker;
+pub mod clock;
+pub mod credential;
 pub mod error;
 pub mod log_service;
+pub mod middleware;
+#[cfg(any(test, feature = "test-util"))]
+pub mod mock;
+pub mod params;
+pub mod rate_limit;
+pub mod retry;
 pub mod roa;
 pub mod rpc;
+pub mod signature;
+pub mod transport;
+
+use reqwest::header::HeaderMap;
+
+/// The headers set by the body methods of clients.
+const BODY_HEADERS: &[&str] = &["content-length", "content-md5", "content-type"];
+
+/// Replace the headers, but keep the headers of body which are not in the new headers.
+pub(crate) fn replace_headers(headers: &mut HeaderMap, mut new_headers: HeaderMap) {
+    for name in BODY_HEADERS {
+        if let Some(value) = headers.get(*name) {
+            if !new_headers.contains_key(*name) {
+                new_headers.insert(*name, value.clone());
+            }
+        }
+    }
+
+    *headers = new_headers;
+}
@@ -0,0 +1,546 @@
+//! Flatten request structs into the params of RPC api.
+//!
+//! The nested fields are joined with `.`, and the elements of lists are numbered from 1,
+//! for example `Tag.1.Key=k&Tag.1.Value=v`. The fields and elements of `None` are skipped.
+//! The unit variants of enums are serialized as their names, other variants are not supported.
+//!
+//! ```rust
+//! use aliyun_openapi_core_rust_sdk::client::params::{json_string, to_params};
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize)]
+//! #[serde(rename_all = "PascalCase")]
+//! struct Tag {
+//!     key: String,
+//!     value: String,
+//! }
+//!
+//! #[derive(Serialize)]
+//! #[serde(rename_all = "PascalCase")]
+//! struct Request {
+//!     region_id: String,
+//!     instance_ids: Vec<String>,
+//!     tag: Vec<Tag>,
+//!     #[serde(serialize_with = "json_string")]
+//!     filters: Vec<String>,
+//! }
+//!
+//! let params = to_params(&Request {
+//!     region_id: "cn-hangzhou".to_string(),
+//!     instance_ids: vec!["i-1".to_string()],
+//!     tag: vec![Tag {
+//!         key: "k".to_string(),
+//!         value: "v".to_string(),
+//!     }],
+//!     filters: vec!["f".to_string()],
+//! })
+//! .unwrap();
+//!
+//! assert_eq!(
+//!     params,
+//!     vec![
+//!         ("RegionId".to_string(), "cn-hangzhou".to_string()),
+//!         ("InstanceIds.1".to_string(), "i-1".to_string()),
+//!         ("Tag.1.Key".to_string(), "k".to_string()),
+//!         ("Tag.1.Value".to_string(), "v".to_string()),
+//!         ("Filters".to_string(), r#"["f"]"#.to_string()),
+//!     ]
+//! );
+//! ```
+
+use std::fmt::Display;
+
+use serde::{
+    ser::{self, Error as _, Impossible},
+    Serialize, Serializer,
+};
+use serde_json::Value;
+
+use crate::client::error::{Error, Result};
+
+/// The error to serialize params.
+#[derive(thiserror::Error, Debug)]
+#[error("Cannot serialize params: {0}")]
+struct ParamsError(String);
+
+impl ser::Error for ParamsError {
+    fn custom<T: Display>(msg: T) -> Self {
+        ParamsError(msg.to_string())
+    }
+}
+
+type ParamsResult<T> = std::result::Result<T, ParamsError>;
+
+/// Flatten a struct or map into the params of RPC api.
+pub fn to_params<T: Serialize + ?Sized>(value: &T) -> Result<Vec<(String, String)>> {
+    let mut params = Vec::new();
+    value
+        .serialize(ParamsSerializer {
+            key: String::new(),
+            params: &mut params,
+        })
+        .map_err(|e| Error::InvalidRequest(e.to_string()))?;
+
+    Ok(params)
+}
+
+/// Serialize a field as JSON string instead of flattening it.
+///
+/// Use it with `#[serde(serialize_with = "json_string")]` for the params in JSON style.
+pub fn json_string<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize + ?Sized,
+    S: Serializer,
+{
+    let value = serde_json::to_string(value).map_err(ser::Error::custom)?;
+    serializer.serialize_str(&value)
+}
+
+/// The serializer to flatten a value into params with the `key`.
+struct ParamsSerializer<'a> {
+    /// The key of value, empty for the top-level value.
+    key: String,
+    params: &'a mut Vec<(String, String)>,
+}
+
+impl<'a> ParamsSerializer<'a> {
+    /// Add a param of scalar value.
+    fn push(self, value: impl ToString) -> ParamsResult<()> {
+        if self.key.is_empty() {
+            return Err(ParamsError::custom("params must be a struct or map"));
+        }
+        self.params.push((self.key, value.to_string()));
+
+        Ok(())
+    }
+
+    /// Flatten the fields or elements of a compound value.
+    fn compound(self) -> ParamsResult<Compound<'a>> {
+        Ok(Compound {
+            key: self.key,
+            index: 0,
+            map_key: None,
+            params: self.params,
+        })
+    }
+}
+
+impl<'a> Serializer for ParamsSerializer<'a> {
+    type Ok = ();
+    type Error = ParamsError;
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = Impossible<(), ParamsError>;
+    type SerializeMap = Compound<'a>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Impossible<(), ParamsError>;
+
+    fn serialize_bool(self, v: bool) -> ParamsResult<()> {
+        self.push(v)
+    }
+
+    fn serialize_i8(self, v: i8) -> ParamsResult<()> {
+        self.push(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> ParamsResult<()> {
+        self.push(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> ParamsResult<()> {
+        self.push(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> ParamsResult<()> {
+        self.push(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> ParamsResult<()> {
+        self.push(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> ParamsResult<()> {
+        self.push(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> ParamsResult<()> {
+        self.push(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> ParamsResult<()> {
+        self.push(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> ParamsResult<()> {
+        self.push(v)
+    }
+
+    fn serialize_f64(self, v: f64) -> ParamsResult<()> {
+        self.push(v)
+    }
+
+    fn serialize_char(self, v: char) -> ParamsResult<()> {
+        self.push(v)
+    }
+
+    fn serialize_str(self, v: &str) -> ParamsResult<()> {
+        self.push(v)
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> ParamsResult<()> {
+        Err(ParamsError::custom("bytes are not supported"))
+    }
+
+    fn serialize_none(self) -> ParamsResult<()> {
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> ParamsResult<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> ParamsResult<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> ParamsResult<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> ParamsResult<()> {
+        self.push(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> ParamsResult<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _value: &T,
+    ) -> ParamsResult<()> {
+        Err(ParamsError::custom(format!(
+            "newtype variant {variant} is not supported"
+        )))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> ParamsResult<Compound<'a>> {
+        self.compound()
+    }
+
+    fn serialize_tuple(self, _len: usize) -> ParamsResult<Compound<'a>> {
+        self.compound()
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> ParamsResult<Compound<'a>> {
+        self.compound()
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> ParamsResult<Self::SerializeTupleVariant> {
+        Err(ParamsError::custom(format!(
+            "tuple variant {variant} is not supported"
+        )))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> ParamsResult<Compound<'a>> {
+        self.compound()
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> ParamsResult<Compound<'a>> {
+        self.compound()
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> ParamsResult<Self::SerializeStructVariant> {
+        Err(ParamsError::custom(format!(
+            "struct variant {variant} is not supported"
+        )))
+    }
+}
+
+/// The serializer of the fields, entries or elements of a compound value.
+struct Compound<'a> {
+    /// The key of compound value, empty for the top-level value.
+    key: String,
+    /// The index of the last element of list which has params.
+    index: usize,
+    /// The key of the map entry being serialized.
+    map_key: Option<String>,
+    params: &'a mut Vec<(String, String)>,
+}
+
+impl<'a> Compound<'a> {
+    /// Serialize the `value` of the child `key`.
+    fn child<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> ParamsResult<()> {
+        let key = if self.key.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", self.key, key)
+        };
+
+        value.serialize(ParamsSerializer {
+            key,
+            params: self.params,
+        })
+    }
+
+    /// Serialize the next element of list, the elements are numbered from 1.
+    ///
+    /// The elements without params, such as `None`, are skipped without taking an index.
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> ParamsResult<()> {
+        if self.key.is_empty() {
+            return Err(ParamsError::custom("params must be a struct or map"));
+        }
+        let len = self.params.len();
+        self.child(&(self.index + 1).to_string(), value)?;
+        if self.params.len() > len {
+            self.index += 1;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeSeq for Compound<'a> {
+    type Ok = ();
+    type Error = ParamsError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> ParamsResult<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> ParamsResult<()> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeTuple for Compound<'a> {
+    type Ok = ();
+    type Error = ParamsError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> ParamsResult<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> ParamsResult<()> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for Compound<'a> {
+    type Ok = ();
+    type Error = ParamsError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> ParamsResult<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> ParamsResult<()> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeMap for Compound<'a> {
+    type Ok = ();
+    type Error = ParamsError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> ParamsResult<()> {
+        let key = match serde_json::to_value(key).map_err(ParamsError::custom)? {
+            Value::String(key) => key,
+            Value::Number(key) => key.to_string(),
+            Value::Bool(key) => key.to_string(),
+            _ => return Err(ParamsError::custom("map key must be a string or number")),
+        };
+        self.map_key = Some(key);
+
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> ParamsResult<()> {
+        let key = self
+            .map_key
+            .take()
+            .ok_or_else(|| ParamsError::custom("map value is serialized before key"))?;
+
+        self.child(&key, value)
+    }
+
+    fn end(self) -> ParamsResult<()> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeStruct for Compound<'a> {
+    type Ok = ();
+    type Error = ParamsError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> ParamsResult<()> {
+        self.child(key, value)
+    }
+
+    fn end(self) -> ParamsResult<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::Serialize;
+
+    use super::*;
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Tag {
+        key: String,
+        value: Option<String>,
+    }
+
+    #[derive(Serialize)]
+    enum Status {
+        Running,
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Request {
+        page_size: u32,
+        dry_run: bool,
+        status: Status,
+        instance_ids: Vec<String>,
+        tag: Vec<Tag>,
+        labels: BTreeMap<String, i32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        next_token: Option<String>,
+        #[serde(serialize_with = "json_string")]
+        filter: BTreeMap<String, Vec<String>>,
+    }
+
+    #[test]
+    fn to_params_test() -> Result<()> {
+        let request = Request {
+            page_size: 10,
+            dry_run: false,
+            status: Status::Running,
+            instance_ids: vec!["i-1".to_string(), "i-2".to_string()],
+            tag: vec![
+                Tag {
+                    key: "env".to_string(),
+                    value: Some("prod".to_string()),
+                },
+                Tag {
+                    key: "team".to_string(),
+                    value: None,
+                },
+            ],
+            labels: BTreeMap::from([("a".to_string(), 1)]),
+            next_token: None,
+            filter: BTreeMap::from([("Status".to_string(), vec!["Running".to_string()])]),
+        };
+
+        let params = to_params(&request)?;
+        let params: Vec<(&str, &str)> = params
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        assert_eq!(
+            params,
+            vec![
+                ("PageSize", "10"),
+                ("DryRun", "false"),
+                ("Status", "Running"),
+                ("InstanceIds.1", "i-1"),
+                ("InstanceIds.2", "i-2"),
+                ("Tag.1.Key", "env"),
+                ("Tag.1.Value", "prod"),
+                ("Tag.2.Key", "team"),
+                ("Labels.a", "1"),
+                ("Filter", r#"{"Status":["Running"]}"#),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn to_params_none_element_test() -> Result<()> {
+        #[derive(Serialize)]
+        #[serde(rename_all = "PascalCase")]
+        struct Request {
+            instance_ids: Vec<Option<String>>,
+        }
+
+        let request = Request {
+            instance_ids: vec![None, Some("i-1".to_string()), None, Some("i-2".to_string())],
+        };
+        assert_eq!(
+            to_params(&request)?,
+            vec![
+                ("InstanceIds.1".to_string(), "i-1".to_string()),
+                ("InstanceIds.2".to_string(), "i-2".to_string()),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn to_params_invalid_test() {
+        #[derive(Serialize)]
+        enum Filter {
+            Name(String),
+        }
+
+        #[derive(Serialize)]
+        #[serde(rename_all = "PascalCase")]
+        struct Request {
+            filter: Filter,
+        }
+
+        assert!(matches!(to_params("value"), Err(Error::InvalidRequest(_))));
+        assert!(matches!(
+            to_params(&vec!["value"]),
+            Err(Error::InvalidRequest(_))
+        ));
+        assert!(matches!(
+            to_params(&Request {
+                filter: Filter::Name("name".to_string()),
+            }),
+            Err(Error::InvalidRequest(e)) if e.contains("newtype variant Name is not supported")
+        ));
+    }
+}
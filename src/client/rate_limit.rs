@@ -0,0 +1,217 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use tokio::time::{sleep, Instant};
+use url::Url;
+
+/// A token bucket which refills `rate` tokens per second, up to `burst` tokens.
+#[derive(Debug)]
+struct Bucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(rate: f64, burst: u32) -> Self {
+        assert!(rate > 0.0, "rate must be positive");
+        let burst = f64::from(burst.max(1));
+        Bucket {
+            rate,
+            burst,
+            tokens: burst,
+            updated: Instant::now(),
+        }
+    }
+
+    /// Refill tokens by the elapsed time.
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.updated = now;
+    }
+
+    /// The time to wait until a token is available.
+    fn wait_time(&self) -> Duration {
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - self.tokens) / self.rate)
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Buckets {
+    endpoints: HashMap<String, Bucket>,
+    /// The buckets by endpoint host and action.
+    actions: HashMap<(String, String), Bucket>,
+}
+
+/// A client-side rate limiter with token buckets per endpoint and per action of endpoint.
+///
+/// The limiter is shared by clones, so a limiter attached to multiple clients limits
+/// the total rate of them. A request waits until both the bucket of its endpoint and
+/// the bucket of its action have capacity.
+#[derive(Clone, Debug, Default)]
+pub struct RateLimiter {
+    buckets: Arc<Mutex<Buckets>>,
+}
+
+impl RateLimiter {
+    /// Create a rate limiter without limits.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Limit the requests to the endpoint host to `rate` per second, allowing bursts of
+    /// `burst` requests.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rate` is not positive.
+    pub fn endpoint_limit(self, endpoint: impl AsRef<str>, rate: f64, burst: u32) -> Self {
+        self.lock()
+            .endpoints
+            .insert(endpoint_host(endpoint.as_ref()), Bucket::new(rate, burst));
+
+        self
+    }
+
+    /// Limit the requests of the action to the endpoint host to `rate` per second, allowing
+    /// bursts of `burst` requests. The same action of other endpoints is not limited, as the
+    /// products have their own quotas.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rate` is not positive.
+    pub fn action_limit(
+        self,
+        endpoint: impl AsRef<str>,
+        action: impl Into<String>,
+        rate: f64,
+        burst: u32,
+    ) -> Self {
+        self.lock().actions.insert(
+            (endpoint_host(endpoint.as_ref()), action.into()),
+            Bucket::new(rate, burst),
+        );
+
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Buckets> {
+        self.buckets.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Wait until the request of the action to the endpoint is allowed.
+    pub(crate) async fn acquire(&self, endpoint: &str, action: &str) {
+        let host = endpoint_host(endpoint);
+        let key = (host, action.to_string());
+        loop {
+            let wait_time = {
+                let mut buckets = self.lock();
+                let Buckets { endpoints, actions } = &mut *buckets;
+                let mut limited: Vec<&mut Bucket> = endpoints
+                    .get_mut(&key.0)
+                    .into_iter()
+                    .chain(actions.get_mut(&key))
+                    .collect();
+
+                let now = Instant::now();
+                limited.iter_mut().for_each(|bucket| bucket.refill(now));
+                let wait_time = limited
+                    .iter()
+                    .map(|bucket| bucket.wait_time())
+                    .max()
+                    .unwrap_or_default();
+                if wait_time.is_zero() {
+                    limited.iter_mut().for_each(|bucket| bucket.tokens -= 1.0);
+                    return;
+                }
+                wait_time
+            };
+
+            sleep(wait_time).await;
+        }
+    }
+}
+
+/// Get the host of endpoint, or the endpoint itself if it is not a url.
+pub(crate) fn endpoint_host(endpoint: &str) -> String {
+    Url::parse(endpoint)
+        .ok()
+        .and_then(|url| url.host_str().map(ToString::to_string))
+        .unwrap_or_else(|| endpoint.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_limiter_test() {
+        let limiter = RateLimiter::new()
+            .endpoint_limit("ecs.aliyuncs.com", 10.0, 2)
+            .action_limit("ecs.aliyuncs.com", "DescribeInstances", 1.0, 1);
+
+        // the action limit is stricter than the endpoint limit.
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter
+                .acquire("https://ecs.aliyuncs.com/", "DescribeInstances")
+                .await;
+        }
+        assert_eq!(start.elapsed().as_secs(), 2);
+
+        // the endpoint limit applies to all actions.
+        let start = Instant::now();
+        for _ in 0..4 {
+            limiter
+                .acquire("https://ecs.aliyuncs.com/", "DescribeRegions")
+                .await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(200));
+
+        // no limit for other endpoints.
+        let start = Instant::now();
+        for _ in 0..10 {
+            limiter
+                .acquire("https://vpc.aliyuncs.com/", "DescribeVpcs")
+                .await;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_limiter_action_per_endpoint_test() {
+        let limiter = RateLimiter::new()
+            .action_limit("https://ecs.aliyuncs.com/", "DescribeRegions", 1.0, 1)
+            .action_limit("https://rds.aliyuncs.com/", "DescribeRegions", 1.0, 1);
+
+        // the same action of two endpoints is limited separately.
+        let start = Instant::now();
+        for endpoint in ["https://ecs.aliyuncs.com/", "https://rds.aliyuncs.com/"] {
+            limiter.acquire(endpoint, "DescribeRegions").await;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        limiter
+            .acquire("https://ecs.aliyuncs.com/", "DescribeRegions")
+            .await;
+        assert_eq!(start.elapsed().as_secs(), 1);
+
+        // the action of endpoints without limit is not limited.
+        let start = Instant::now();
+        for _ in 0..10 {
+            limiter
+                .acquire("https://vpc.aliyuncs.com/", "DescribeRegions")
+                .await;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,335 @@
+use std::{future::Future, time::Duration};
+
+use rand::Rng;
+use reqwest::{Response, StatusCode};
+
+use crate::client::error::{Error, Result};
+
+/// Default error codes of aliyun api service which are safe to retry.
+const DEFAULT_RETRYABLE_ERROR_CODES: &[&str] = &[
+    "Throttling",
+    "Throttling.Api",
+    "Throttling.User",
+    "ServiceUnavailable",
+    "InternalError",
+    "SystemBusy",
+];
+
+/// Default HTTP status codes which are safe to retry.
+const DEFAULT_RETRYABLE_STATUSES: &[StatusCode] = &[
+    StatusCode::TOO_MANY_REQUESTS,
+    StatusCode::INTERNAL_SERVER_ERROR,
+    StatusCode::BAD_GATEWAY,
+    StatusCode::SERVICE_UNAVAILABLE,
+    StatusCode::GATEWAY_TIMEOUT,
+];
+
+/// The policy to retry failed requests with exponential backoff.
+///
+/// A request is retried when the connection fails, when the HTTP status is retryable,
+/// or when the error code of the response is retryable. Every attempt is signed again
+/// with a fresh nonce and timestamp.
+///
+/// The requests failed after they were sent, such as timeout, may have been processed by
+/// the server, so they are only retried if [`RetryPolicy::retry_sent_requests`] is set.
+/// For the same reason, the non-idempotent `POST` and `PATCH` requests failed with server
+/// errors are only retried for 503 unless it is set, 429 is retried for all methods.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// The max number of attempts, including the first one.
+    max_attempts: u32,
+    /// The backoff before the first retry.
+    initial_backoff: Duration,
+    /// The upper bound of backoff.
+    max_backoff: Duration,
+    /// Whether to randomize the backoff.
+    jitter: bool,
+    /// The error codes of response to retry.
+    retryable_error_codes: Vec<String>,
+    /// The HTTP status codes of response to retry.
+    retryable_statuses: Vec<StatusCode>,
+    /// Whether to retry the requests failed after they were sent.
+    retry_sent_requests: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            jitter: true,
+            retryable_error_codes: DEFAULT_RETRYABLE_ERROR_CODES
+                .iter()
+                .map(|code| code.to_string())
+                .collect(),
+            retryable_statuses: DEFAULT_RETRYABLE_STATUSES.to_vec(),
+            retry_sent_requests: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Create a policy with 3 attempts, which retries throttling and server errors.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Create a policy which never retries.
+    pub fn disabled() -> Self {
+        Self::new().max_attempts(1)
+    }
+
+    /// Set the max number of attempts, including the first one.
+    ///
+    /// Default is 3.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+
+        self
+    }
+
+    /// Set the backoff before the first retry and the upper bound of backoff.
+    ///
+    /// The backoff is doubled after each retry. Default is 100ms and 10s.
+    pub fn backoff(mut self, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self.max_backoff = max_backoff;
+
+        self
+    }
+
+    /// Set whether to randomize the backoff between zero and the computed backoff.
+    ///
+    /// Default is `true`.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+
+        self
+    }
+
+    /// Set the error codes of response to retry.
+    ///
+    /// Default is throttling, `ServiceUnavailable`, `InternalError` and `SystemBusy`.
+    pub fn retryable_error_codes<I, T>(mut self, codes: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.retryable_error_codes = codes.into_iter().map(Into::into).collect();
+
+        self
+    }
+
+    /// Set the HTTP status codes of response to retry.
+    ///
+    /// Default is 429, 500, 502, 503 and 504.
+    pub fn retryable_statuses(mut self, statuses: impl IntoIterator<Item = StatusCode>) -> Self {
+        self.retryable_statuses = statuses.into_iter().collect();
+
+        self
+    }
+
+    /// Set whether to retry the requests failed after they were sent, such as timeout, and
+    /// the `POST` and `PATCH` requests failed with server errors other than 503.
+    ///
+    /// The server may have processed the request, so only set it for idempotent apis,
+    /// or the requests with `ClientToken`. Default is `false`.
+    pub fn retry_sent_requests(mut self, retry_sent_requests: bool) -> Self {
+        self.retry_sent_requests = retry_sent_requests;
+
+        self
+    }
+
+    /// Whether the failed attempt of request with the `method` should be retried.
+    fn is_retryable(&self, method: &str, status: Option<StatusCode>, error: &Error) -> bool {
+        if let Some(status) = status {
+            // the non-idempotent request may have been processed before the server error.
+            let is_idempotent = !matches!(method.to_uppercase().as_str(), "POST" | "PATCH");
+            if status.is_server_error()
+                && status != StatusCode::SERVICE_UNAVAILABLE
+                && !is_idempotent
+                && !self.retry_sent_requests
+            {
+                return false;
+            }
+            if self.retryable_statuses.contains(&status) {
+                return true;
+            }
+        }
+
+        match error {
+            Error::Reqwest(e) => {
+                status.is_none()
+                    && (e.is_connect()
+                        || (self.retry_sent_requests && (e.is_timeout() || e.is_request())))
+            }
+            Error::Timeout(_) => self.retry_sent_requests,
+            Error::InvalidResponse { error_code, .. } => {
+                self.retryable_error_codes.contains(error_code)
+            }
+            _ => false,
+        }
+    }
+
+    /// The backoff before the retry after the `attempt`.
+    fn backoff_of(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(self.max_backoff);
+        if self.jitter && !backoff.is_zero() {
+            rand::thread_rng().gen_range(Duration::ZERO..=backoff)
+        } else {
+            backoff
+        }
+    }
+
+    /// Send a request with retries.
+    ///
+    /// `send` builds, signs and sends a new attempt of request with the `method` and its
+    /// context, `check` turns a response with failed HTTP status into an error.
+    pub(crate) async fn send<T, S, SF, C, CF>(
+        &self,
+        method: &str,
+        mut send: S,
+        check: C,
+    ) -> Result<Response>
+    where
+        S: FnMut() -> SF,
+        SF: Future<Output = Result<(T, Response)>>,
+        C: Fn(T, Response) -> CF,
+        CF: Future<Output = Result<Response>>,
+    {
+        let mut attempt = 1;
+        loop {
+            let (status, result) = match send().await {
+                Ok((context, response)) => {
+                    (Some(response.status()), check(context, response).await)
+                }
+                Err(e) => (None, Err(e)),
+            };
+
+            match result {
+                Err(e) if attempt < self.max_attempts && self.is_retryable(method, status, &e) => {
+                    tokio::time::sleep(self.backoff_of(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_test() {
+        let policy = RetryPolicy::new()
+            .backoff(Duration::from_millis(100), Duration::from_millis(300))
+            .jitter(false);
+        assert_eq!(policy.backoff_of(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff_of(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff_of(3), Duration::from_millis(300));
+        assert_eq!(policy.backoff_of(40), Duration::from_millis(300));
+
+        let policy = policy.jitter(true);
+        for attempt in 1..5 {
+            assert!(policy.backoff_of(attempt) <= Duration::from_millis(300));
+        }
+    }
+
+    #[tokio::test]
+    async fn is_retryable_test() {
+        let policy = RetryPolicy::new();
+        let error = |code: &str| Error::InvalidResponse {
+            request_id: "request-id".to_string(),
+            error_code: code.to_string(),
+            error_message: "message".to_string(),
+        };
+
+        assert!(policy.is_retryable(
+            "GET",
+            Some(StatusCode::BAD_REQUEST),
+            &error("Throttling.User")
+        ));
+        assert!(policy.is_retryable(
+            "GET",
+            Some(StatusCode::SERVICE_UNAVAILABLE),
+            &error("Unknown")
+        ));
+        assert!(!policy.is_retryable(
+            "GET",
+            Some(StatusCode::BAD_REQUEST),
+            &error("InvalidParameter")
+        ));
+        assert!(!policy.is_retryable("GET", None, &Error::InvalidRequest("invalid".to_string())));
+
+        // the POST request may have been processed before the server error.
+        let internal_error = error("InternalError");
+        assert!(policy.is_retryable(
+            "GET",
+            Some(StatusCode::INTERNAL_SERVER_ERROR),
+            &internal_error
+        ));
+        assert!(!policy.is_retryable(
+            "POST",
+            Some(StatusCode::INTERNAL_SERVER_ERROR),
+            &internal_error
+        ));
+        assert!(!policy.is_retryable("POST", Some(StatusCode::BAD_GATEWAY), &error("Unknown")));
+        assert!(policy.is_retryable(
+            "POST",
+            Some(StatusCode::SERVICE_UNAVAILABLE),
+            &error("Unknown")
+        ));
+        assert!(policy.is_retryable(
+            "POST",
+            Some(StatusCode::TOO_MANY_REQUESTS),
+            &error("Unknown")
+        ));
+        assert!(policy.clone().retry_sent_requests(true).is_retryable(
+            "POST",
+            Some(StatusCode::INTERNAL_SERVER_ERROR),
+            &internal_error
+        ));
+
+        // the connection is refused, the request is never sent.
+        let address = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let connect_error: Error = reqwest::get(format!("http://{address}"))
+            .await
+            .unwrap_err()
+            .into();
+        assert!(policy.is_retryable("GET", None, &connect_error));
+
+        // the request is sent, but the server never responds.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let timeout_error: Error = reqwest::Client::new()
+            .get(format!("http://{address}"))
+            .timeout(Duration::from_millis(100))
+            .send()
+            .await
+            .unwrap_err()
+            .into();
+        assert!(!policy.is_retryable("GET", None, &timeout_error));
+        assert!(policy
+            .clone()
+            .retry_sent_requests(true)
+            .is_retryable("GET", None, &timeout_error));
+        drop(listener);
+
+        // the timeout of client request.
+        let timeout_error = Error::Timeout(Duration::from_millis(100));
+        assert!(!policy.is_retryable("GET", None, &timeout_error));
+        assert!(policy
+            .clone()
+            .retry_sent_requests(true)
+            .is_retryable("GET", None, &timeout_error));
+    }
+}
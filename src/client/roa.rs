@@ -1,18 +1,38 @@
-use std::{collections::HashMap, time::Duration};
+use std::{collections::HashMap, error::Error as StdError, sync::Arc, time::Duration};
 
-use hmac::{Hmac, Mac};
+use bytes::Bytes;
+use futures::TryStream;
 use md5::{Digest, Md5};
 use reqwest::{
-    header::{HeaderMap, HeaderValue},
-    ClientBuilder, Response,
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Client, Response,
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use sha1::Sha1;
 use time::{macros::format_description, OffsetDateTime};
-use url::Url;
-use uuid::Uuid;
+use url::{form_urlencoded, Url};
 
-use crate::client::error::{Error, Result};
+use crate::client::{
+    body::Body,
+    circuit_breaker::CircuitBreaker,
+    clock::{
+        is_clock_skew_error, Clock, ClockSkew, NonceGenerator, SystemClock, UuidNonceGenerator,
+    },
+    credential::{
+        Credential, CredentialProvider, DefaultCredentialChain, ProfileProvider,
+        StaticCredentialProvider,
+    },
+    error::{Error, Result},
+    middleware::{Middleware, RequestContext},
+    params::to_params,
+    rate_limit::RateLimiter,
+    replace_headers,
+    retry::RetryPolicy,
+    signature::{
+        authorization_v3, canonical_query_string, check_signer, format_v3_date, hex_hash,
+        SignatureVersion, Signer,
+    },
+    transport::{new_request, HttpTransport, ReqwestTransport},
+};
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "PascalCase")]
@@ -33,34 +53,50 @@ pub struct ROAServiceError {
 const AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
 const DEFAULT_HEADER: &[(&str, &str)] = &[
     ("accept", "application/json"),
-    ("x-acs-signature-method", "HMAC-SHA1"),
     ("user-agent", AGENT),
     ("x-sdk-client", AGENT),
 ];
 
-type HamcSha1 = Hmac<Sha1>;
-
 /// Config for request.
 #[derive(Clone, Debug, Default)]
 struct Request {
     method: String,
     uri: String,
-    body: Option<String>,
+    body: Option<Body>,
     query: Vec<(String, String)>,
     headers: HeaderMap,
     project: Option<String>,
+    action: Option<String>,
     version: String,
     timeout: Option<Duration>,
 }
 
 #[derive(Clone, Debug)]
 pub struct ROAClient {
-    /// The access key id of aliyun developer account.
-    access_key_id: String,
-    /// The access key secret of aliyun developer account.
-    access_key_secret: String,
+    /// The provider of credentials to sign requests.
+    credential_provider: Arc<dyn CredentialProvider>,
     /// The api endpoint of aliyun api service (need start with http:// or https://).
     endpoint: String,
+    /// The version of signature.
+    signature_version: SignatureVersion,
+    /// The algorithm to sign requests.
+    signer: Option<Arc<dyn Signer>>,
+    /// The source of current time to sign requests.
+    clock: Arc<dyn Clock>,
+    /// The offset between local clock and server clock, shared by clones.
+    clock_skew: ClockSkew,
+    /// The source of nonces to sign requests.
+    nonce_generator: Arc<dyn NonceGenerator>,
+    /// The transport to send requests, shared by clones to reuse connections.
+    transport: Arc<dyn HttpTransport>,
+    /// The policy to retry failed requests.
+    retry_policy: RetryPolicy,
+    /// The limiter to throttle requests on client side.
+    rate_limiter: Option<RateLimiter>,
+    /// The circuit breaker to fail fast when the endpoint is unhealthy.
+    circuit_breaker: Option<CircuitBreaker>,
+    /// The middlewares to run around every attempt of request.
+    middlewares: Vec<Arc<dyn Middleware>>,
     /// The config of http request.
     request: Request,
 }
@@ -71,11 +107,50 @@ impl ROAClient {
         access_key_id: impl Into<String>,
         access_key_secret: impl Into<String>,
         endpoint: impl Into<String>,
+    ) -> Self {
+        Self::from_provider(
+            Arc::new(StaticCredentialProvider::new(
+                access_key_id,
+                access_key_secret,
+            )),
+            endpoint,
+        )
+    }
+
+    /// Create a api client with the default credential chain.
+    ///
+    /// See [`DefaultCredentialChain`] for where the credential is loaded from.
+    pub fn from_env(endpoint: impl Into<String>) -> Self {
+        Self::from_provider(Arc::new(DefaultCredentialChain::new()), endpoint)
+    }
+
+    /// Create a api client with the profile of aliyun CLI.
+    ///
+    /// See [`ProfileProvider`] for the supported modes of profile. Only the credential is
+    /// read from the profile, the region of ROA APIs is a part of `endpoint` or the path, read the `region_id` of the profile by
+    /// [`ProfileProvider::region_id`] to build it if needed.
+    pub fn from_profile(profile: impl Into<String>, endpoint: impl Into<String>) -> Self {
+        Self::from_provider(Arc::new(ProfileProvider::new().profile(profile)), endpoint)
+    }
+
+    /// Create a api client with the credential provider.
+    pub fn from_provider(
+        credential_provider: Arc<dyn CredentialProvider>,
+        endpoint: impl Into<String>,
     ) -> Self {
         ROAClient {
-            access_key_id: access_key_id.into(),
-            access_key_secret: access_key_secret.into(),
+            credential_provider,
             endpoint: endpoint.into(),
+            signature_version: Default::default(),
+            signer: None,
+            clock: Arc::new(SystemClock),
+            clock_skew: Default::default(),
+            nonce_generator: Arc::new(UuidNonceGenerator),
+            transport: Arc::new(ReqwestTransport::default()),
+            retry_policy: RetryPolicy::disabled(),
+            rate_limiter: None,
+            circuit_breaker: None,
+            middlewares: Vec::new(),
             request: Default::default(),
         }
     }
@@ -104,7 +179,7 @@ impl ROAClient {
         self.request("POST".to_string(), uri.into())
     }
 
-    /// Set queries for request.
+    /// Set queries for request, the queries set before are replaced.
     ///
     /// Returns a `Self` for send request.
     pub fn query<I, T>(mut self, queries: I) -> Self
@@ -120,6 +195,29 @@ impl ROAClient {
         self
     }
 
+    /// Add a query for request. If the query `key` is set, its value is replaced.
+    ///
+    /// Returns a `Self` for send request.
+    pub fn add_query(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let (key, value) = (key.into(), value.into());
+        match self.request.query.iter_mut().find(|(k, _)| *k == key) {
+            Some(query) => query.1 = value,
+            None => self.request.query.push((key, value)),
+        }
+
+        self
+    }
+
+    /// Add a query for request if the `value` is `Some`, see [`Self::add_query`].
+    ///
+    /// Returns a `Self` for send request.
+    pub fn query_opt(self, key: impl Into<String>, value: Option<impl Into<String>>) -> Self {
+        match value {
+            Some(value) => self.add_query(key, value),
+            None => self,
+        }
+    }
+
     /// Set version for request.
     ///
     /// Returns a `Self` for send request.
@@ -129,37 +227,237 @@ impl ROAClient {
         self
     }
 
+    /// Set api name for request, which is required by V3 signature.
+    ///
+    /// Returns a `Self` for send request.
+    pub fn action(mut self, action: impl Into<String>) -> Self {
+        self.request.action = Some(action.into());
+
+        self
+    }
+
+    /// Set the version of signature.
+    ///
+    /// Default is [`SignatureVersion::V1`].
+    pub fn signature_version(mut self, signature_version: SignatureVersion) -> Self {
+        self.signature_version = signature_version;
+
+        self
+    }
+
+    /// Set the algorithm to sign requests.
+    ///
+    /// Default is `HMAC-SHA1` for V1 signature and `HMAC-SHA256` for V3 signature.
+    pub fn signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.signer = Some(signer);
+
+        self
+    }
+
+    /// Set the source of current time to sign requests.
+    ///
+    /// Default is [`SystemClock`].
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+
+        self
+    }
+
+    /// Set the source of nonces to sign requests.
+    ///
+    /// Default is [`UuidNonceGenerator`].
+    pub fn nonce_generator(mut self, nonce_generator: Arc<dyn NonceGenerator>) -> Self {
+        self.nonce_generator = nonce_generator;
+
+        self
+    }
+
+    /// Set the http client to send requests.
+    ///
+    /// The connection pool of the http client is shared by all requests and clones of
+    /// this client. Default is a `reqwest::Client` with default configs.
+    pub fn http_client(mut self, http_client: Client) -> Self {
+        self.transport = Arc::new(ReqwestTransport::new(http_client));
+
+        self
+    }
+
+    /// Set the transport to send signed requests.
+    ///
+    /// Default is [`ReqwestTransport`].
+    pub fn transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+
+        self
+    }
+
+    /// Set the policy to retry failed requests.
+    ///
+    /// Default is [`RetryPolicy::disabled`], which never retries.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+
+        self
+    }
+
+    /// Set the limiter to throttle requests on client side.
+    ///
+    /// Every attempt of request waits until the limits of the endpoint and the action allow it,
+    /// the `uri` is used as the action if [`ROAClient::action`] is not set.
+    pub fn rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+
+        self
+    }
+
+    /// Set the circuit breaker to fail fast when the endpoint is unhealthy.
+    ///
+    /// Transport errors and 5xx responses are counted as failures of the endpoint.
+    pub fn circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(circuit_breaker);
+
+        self
+    }
+
+    /// Add a middleware to run around every attempt of request.
+    ///
+    /// See [`Middleware`] for the order of middlewares.
+    pub fn middleware(mut self, middleware: Arc<dyn Middleware>) -> Self {
+        self.middlewares.push(middleware);
+
+        self
+    }
+
     /// Set body for request.
     ///
     /// Returns a `Self` for send request.
-    pub fn body(mut self, body: impl Into<String>) -> Result<Self> {
+    pub fn body(self, body: impl Into<String>) -> Result<Self> {
+        self.binary_body(body.into())
+    }
+
+    /// Set binary body for request, such as images, protobuf or compressed data.
+    ///
+    /// Returns a `Self` for send request.
+    pub fn binary_body(mut self, body: impl Into<Bytes>) -> Result<Self> {
         // compute body length and md5.
         let body = body.into();
         let mut hasher = Md5::new();
-        hasher.update(body.as_bytes());
+        hasher.update(&body);
         let md5_result = hasher.finalize();
 
         // update headers.
+        self.content_headers(body.len() as u64, &md5_result)?;
+
+        // store body bytes.
+        self.request.body = Some(Body::Bytes(body));
+
+        Ok(self)
+    }
+
+    /// Set streaming body for request, so a large body is not held in memory.
+    ///
+    /// The `stream` is called to create the body for every attempt of request, and the
+    /// `content_length` and the MD5 digest `content_md5` of body must be computed in advance.
+    ///
+    /// The V3 signature hashes the whole body, so streaming body is only supported by
+    /// V1 signature, sending it with V3 signature fails with [`Error::InvalidRequest`].
+    ///
+    /// Returns a `Self` for send request.
+    pub fn stream_body<F, S>(
+        mut self,
+        stream: F,
+        content_length: u64,
+        content_md5: [u8; 16],
+    ) -> Result<Self>
+    where
+        F: Fn() -> S + Send + Sync + 'static,
+        S: TryStream + Send + Sync + 'static,
+        S::Error: Into<Box<dyn StdError + Send + Sync>>,
+        Bytes: From<S::Ok>,
+    {
+        self.content_headers(content_length, &content_md5)?;
+        self.request.body = Some(Body::stream(stream));
+
+        Ok(self)
+    }
+
+    /// Set the `content-length` and `content-md5` headers of body.
+    fn content_headers(&mut self, content_length: u64, content_md5: &[u8]) -> Result<()> {
         self.request
             .headers
-            .insert("content-length", body.len().to_string().parse()?);
+            .insert("content-length", content_length.to_string().parse()?);
         self.request
             .headers
-            .insert("content-md5", base64::encode(md5_result).parse()?);
+            .insert("content-md5", base64::encode(content_md5).parse()?);
 
-        // store body string.
-        self.request.body = Some(body);
+        Ok(())
+    }
+
+    /// Set body for request with a value serialized as JSON, and set the `content-type`
+    /// header to `application/json`.
+    ///
+    /// Returns a `Self` for send request.
+    pub fn json_body<T: Serialize + ?Sized>(self, body: &T) -> Result<Self> {
+        let body = serde_json::to_string(body)
+            .map_err(|e| Error::InvalidRequest(format!("Cannot serialize JSON body: {e}")))?;
+
+        self.content_type("application/json")?.body(body)
+    }
+
+    /// Set body for request with a value flattened by [`to_params`] as form, and set the
+    /// `content-type` header to `application/x-www-form-urlencoded`.
+    ///
+    /// Returns a `Self` for send request.
+    pub fn form_body<T: Serialize + ?Sized>(self, body: &T) -> Result<Self> {
+        let body = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(to_params(body)?)
+            .finish();
+
+        self.content_type("application/x-www-form-urlencoded")?
+            .body(body)
+    }
+
+    /// Set body for request with a value serialized as XML, and set the `content-type`
+    /// header to `application/xml`.
+    ///
+    /// Returns a `Self` for send request.
+    pub fn xml_body<T: Serialize + ?Sized>(self, body: &T) -> Result<Self> {
+        let body = quick_xml::se::to_string(body)
+            .map_err(|e| Error::InvalidRequest(format!("Cannot serialize XML body: {e}")))?;
+
+        self.content_type("application/xml")?.body(body)
+    }
+
+    /// Set the `content-type` header of body.
+    fn content_type(mut self, content_type: &str) -> Result<Self> {
+        self.request
+            .headers
+            .insert("content-type", content_type.parse()?);
 
         Ok(self)
     }
 
-    /// Set header for request.
+    /// Set header for request, the headers set before are replaced, except the
+    /// `content-length`, `content-md5` and `content-type` headers set by the body methods,
+    /// which are kept unless they are in `headers`.
     ///
     /// Returns a `Self` for send request.
     pub fn header(mut self, headers: impl Into<HashMap<String, String>>) -> Result<Self> {
-        self.request.headers = (&headers.into())
+        let headers = (&headers.into())
             .try_into()
             .map_err(|e| Error::InvalidRequest(format!("Cannot parse header: {}", e)))?;
+        replace_headers(&mut self.request.headers, headers);
+        Ok(self)
+    }
+
+    /// Add a header for request. If the header `key` is set, its value is replaced.
+    ///
+    /// Returns a `Self` for send request.
+    pub fn add_header(mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> Result<Self> {
+        let key = HeaderName::from_bytes(key.as_ref().as_bytes())
+            .map_err(|e| Error::InvalidRequest(format!("Cannot parse header: {e}")))?;
+        self.request.headers.insert(key, value.as_ref().parse()?);
+
         Ok(self)
     }
 
@@ -172,7 +470,7 @@ impl ROAClient {
         self
     }
 
-    /// Set a timeout for connect, read and write operations of a `Client`.
+    /// Set a timeout for connect, read and write operations of a request.
     ///
     /// Default is no timeout.
     pub fn timeout(mut self, timeout: Duration) -> Self {
@@ -193,9 +491,99 @@ impl ROAClient {
         Ok(self.send().await?.text().await?)
     }
 
-    /// Send a request to service.
+    /// Send a request to service, retry the failed attempts following the retry policy.
     /// Return client Response.
-    pub async fn send(mut self) -> Result<Response> {
+    ///
+    /// If the failure is caused by clock skew, the offset of local clock is corrected with
+    /// the `Date` header of response, and the request is retried once.
+    pub async fn send(self) -> Result<Response> {
+        let offset = self.clock_skew.offset();
+        let result = self.send_with_retry().await;
+
+        // retry once with corrected time if the request failed by clock skew.
+        match result {
+            Err(e) if is_clock_skew_error(&e) && self.clock_skew.offset() != offset => {
+                self.send_with_retry().await
+            }
+            result => result,
+        }
+    }
+
+    /// Send a request, retry the failed attempts following the retry policy.
+    async fn send_with_retry(&self) -> Result<Response> {
+        self.retry_policy
+            .send(
+                &self.request.method,
+                || self.clone().send_once(),
+                |context, response| self.check_response(context, response),
+            )
+            .await
+    }
+
+    /// Get the current time, corrected with the detected clock skew.
+    fn now(&self) -> OffsetDateTime {
+        self.clock.now() + self.clock_skew.offset()
+    }
+
+    /// Build the context of request for middlewares.
+    fn context(&self) -> RequestContext {
+        RequestContext {
+            method: self.request.method.clone(),
+            endpoint: self.endpoint.clone(),
+            action: self.request.action.clone(),
+            uri: self.request.uri.clone(),
+            version: self.request.version.clone(),
+            query: self.request.query.clone(),
+            headers: self.request.headers.clone(),
+        }
+    }
+
+    /// Apply the context of request changed by middlewares.
+    fn apply_context(&mut self, context: RequestContext) {
+        self.request.method = context.method;
+        self.endpoint = context.endpoint;
+        self.request.action = context.action;
+        self.request.uri = context.uri;
+        self.request.version = context.version;
+        self.request.query = context.query;
+        self.request.headers = context.headers;
+    }
+
+    /// Build, sign and send a single attempt of request.
+    /// Return the context of request and the response.
+    async fn send_once(mut self) -> Result<(RequestContext, Response)> {
+        // run middlewares before signing.
+        let mut context = self.context();
+        for middleware in self.middlewares.iter() {
+            middleware.before_sign(&mut context).await?;
+        }
+        self.apply_context(context.clone());
+
+        // pass the error to middlewares if the attempt fails before the response arrives.
+        let middlewares = self.middlewares.clone();
+        match self.sign_and_send(&context).await {
+            Ok(response) => Ok((context, response)),
+            Err(e) => {
+                for middleware in middlewares.iter().rev() {
+                    middleware.after_response(&context, Err(&e)).await;
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Sign and send the request changed by middlewares.
+    async fn sign_and_send(mut self, context: &RequestContext) -> Result<Response> {
+        // wait for the rate limiter.
+        if let Some(rate_limiter) = self.rate_limiter.as_ref() {
+            rate_limiter
+                .acquire(
+                    &self.endpoint,
+                    self.request.action.as_deref().unwrap_or(&self.request.uri),
+                )
+                .await;
+        }
+
         // add const header
         for (k, v) in DEFAULT_HEADER.iter() {
             self.request.headers.insert(*k, v.parse()?);
@@ -209,68 +597,186 @@ impl ROAClient {
             .ok_or_else(|| Error::InvalidRequest(format!("Invalid endpoint: {endpoint}")))?;
         self.request.headers.insert("host", host.parse()?);
 
+        // add api version and name header.
+        if !self.request.version.is_empty() {
+            self.request
+                .headers
+                .insert("x-acs-version", self.request.version.parse()?);
+        }
+        if let Some(action) = self.request.action.as_ref() {
+            self.request.headers.insert("x-acs-action", action.parse()?);
+        }
+
+        // sign request.
+        let credential = self.credential_provider.get_credentials().await?;
+        let signer = self
+            .signer
+            .clone()
+            .unwrap_or_else(|| self.signature_version.default_signer());
+        match self.signature_version {
+            SignatureVersion::V1 => self.sign_v1(signer.as_ref(), &credential)?,
+            SignatureVersion::V3 => self.sign_v3(signer.as_ref(), &credential)?,
+        }
+
+        // build http request.
+        let mut final_url = format!("{}{}", self.endpoint, self.request.uri);
+        if self.signature_version == SignatureVersion::V3 && !self.request.query.is_empty() {
+            // the query of V3 signature is encoded following RFC3986.
+            final_url = format!(
+                "{}?{}",
+                final_url,
+                canonical_query_string(&self.request.query)
+            );
+        }
+        let query = match self.signature_version {
+            SignatureVersion::V1 => self.request.query.as_slice(),
+            SignatureVersion::V3 => &[],
+        };
+        let mut request = new_request(
+            &self.request.method,
+            &final_url,
+            query,
+            self.request.headers,
+            self.request.body,
+        )?;
+
+        // run middlewares after signing.
+        for middleware in self.middlewares.iter() {
+            middleware.after_sign(context, &mut request).await?;
+        }
+
+        // check the circuit breaker.
+        if let Some(circuit_breaker) = self.circuit_breaker.as_ref() {
+            circuit_breaker.acquire(&self.endpoint)?;
+        }
+
+        // send request.
+        let result = self.transport.execute(request, self.request.timeout).await;
+        if let Some(circuit_breaker) = self.circuit_breaker.as_ref() {
+            let success = matches!(&result, Ok(response) if !response.status().is_server_error());
+            circuit_breaker.record(&self.endpoint, success);
+        }
+
+        result
+    }
+
+    /// Check HTTP StatusCode of response, and pass the result to middlewares.
+    async fn check_response(
+        &self,
+        context: RequestContext,
+        response: Response,
+    ) -> Result<Response> {
+        let date = response.headers().get("date").cloned();
+        let result = Self::error_for_status(response).await;
+        if let Err(e) = result.as_ref() {
+            self.clock_skew
+                .detect(self.clock.as_ref(), date.as_ref(), e);
+        }
+        for middleware in self.middlewares.iter().rev() {
+            middleware.after_response(&context, result.as_ref()).await;
+        }
+
+        result
+    }
+
+    /// Turn a response with failed HTTP status into an error.
+    async fn error_for_status(response: Response) -> Result<Response> {
+        if !response.status().is_success() {
+            let result = response.json::<ROAServiceError>().await?;
+            return Err(Error::InvalidResponse {
+                request_id: result.request_id,
+                error_code: result.code,
+                error_message: result.message,
+            });
+        }
+
+        Ok(response)
+    }
+
+    /// Sign request with V1 signature, add signed headers.
+    fn sign_v1(&mut self, signer: &dyn Signer, credential: &Credential) -> Result<()> {
+        check_signer(signer, SignatureVersion::V1)?;
+
         // add date header.
         // RFC 1123: %a, %d %b %Y %H:%M:%S GMT
         let format = format_description!(
             "[weekday repr:short], [day] [month repr:short] [year] [hour]:[minute]:[second] GMT"
         );
-        let ts = OffsetDateTime::now_utc()
+        let ts = self
+            .now()
             .format(&format)
             .map_err(|e| Error::InvalidRequest(format!("Invalid RFC 1123 Date: {}", e)))?;
         self.request.headers.insert("date", ts.parse()?);
 
         // add nonce header.
-        let nonce = Uuid::new_v4().to_string();
+        let nonce = self.nonce_generator.nonce();
         self.request
             .headers
             .insert("x-acs-signature-nonce", nonce.parse()?);
+        self.request
+            .headers
+            .insert("x-acs-signature-method", signer.name().parse()?);
+
+        // add security token header.
+        if let Some(security_token) = credential.security_token.as_ref() {
+            self.request
+                .headers
+                .insert("x-acs-security-token", security_token.parse()?);
+        }
 
         // compute `Authorization` field.
         // Authorization = "acs <AccessKeyId>:<Signature>"
-        let authorization = format!("acs {}:{}", self.access_key_id, self.signature()?);
+        let authorization = format!(
+            "acs {}:{}",
+            credential.access_key_id,
+            self.signature(signer, credential)?
+        );
         self.request
             .headers
             .insert("Authorization", authorization.parse()?);
 
-        // build http client.
-        let final_url = format!("{}{}", self.endpoint, self.request.uri);
-        let mut http_client_builder = ClientBuilder::new();
-        if let Some(timeout) = self.request.timeout {
-            http_client_builder = http_client_builder.timeout(timeout);
-        }
-        let mut http_client = http_client_builder.build()?.request(
-            self.request
-                .method
-                .parse()
-                .map_err(|e| Error::InvalidRequest(format!("Invalid HTTP method: {}", e)))?,
-            &final_url,
-        );
+        Ok(())
+    }
 
-        // set body.
-        if let Some(body) = self.request.body {
-            http_client = http_client.body(body);
+    /// Sign request with V3 signature, add signed headers.
+    fn sign_v3(&mut self, signer: &dyn Signer, credential: &Credential) -> Result<()> {
+        check_signer(signer, SignatureVersion::V3)?;
+        if self.request.action.is_none() {
+            return Err(Error::InvalidRequest(
+                "action is required by V3 signature".to_string(),
+            ));
         }
 
-        // set query.
-        if !self.request.query.is_empty() {
-            http_client = http_client.query(&self.request.query);
+        let nonce = self.nonce_generator.nonce();
+        let ts = format_v3_date(self.now())?;
+        let content_hash = match self.request.body.as_ref() {
+            Some(Body::Stream(_)) => {
+                return Err(Error::InvalidRequest(
+                    "Streaming body is not supported by V3 signature".to_string(),
+                ))
+            }
+            body => hex_hash(signer, body.and_then(Body::as_bytes).unwrap_or_default())?,
+        };
+        let headers = &mut self.request.headers;
+        headers.insert("x-acs-date", ts.parse()?);
+        headers.insert("x-acs-signature-nonce", nonce.parse()?);
+        headers.insert(signer.content_hash_header(), content_hash.parse()?);
+        if let Some(security_token) = credential.security_token.as_ref() {
+            headers.insert("x-acs-security-token", security_token.parse()?);
         }
 
-        // send request.
-        let response = http_client.headers(self.request.headers).send().await?;
-
-        // check HTTP StatusCode.
-        if !response.status().is_success() {
-            let result = response.json::<ROAServiceError>().await?;
-            return Err(Error::InvalidResponse {
-                request_id: result.request_id,
-                error_code: result.code,
-                error_message: result.message,
-            });
-        }
+        // compute `Authorization` field.
+        let authorization = authorization_v3(
+            signer,
+            credential,
+            &self.request.method,
+            &self.request.uri,
+            &self.request.query,
+            headers,
+        )?;
+        headers.insert("Authorization", authorization.parse()?);
 
-        // return response.
-        Ok(response)
+        Ok(())
     }
 
     /// Compute canonicalized headers.
@@ -312,7 +818,7 @@ impl ROAClient {
     }
 
     /// Compute signature for request.
-    fn signature(&self) -> Result<String> {
+    fn signature(&self, signer: &dyn Signer, credential: &Credential) -> Result<String> {
         // build body.
         let canonicalized_headers = self.canonicalized_headers();
         let canonicalized_resource = self.canonicalized_resource();
@@ -338,11 +844,7 @@ impl ROAClient {
         );
 
         // sign body.
-        let mut mac = HamcSha1::new_from_slice(self.access_key_secret.as_bytes())
-            .map_err(|e| Error::InvalidRequest(format!("Invalid HMAC-SHA1 secret key: {}", e)))?;
-        mac.update(body.as_bytes());
-        let result = mac.finalize();
-        let code = result.into_bytes();
+        let code = signer.sign(&credential.access_key_secret, body.as_bytes())?;
 
         Ok(base64::encode(code))
     }
@@ -355,6 +857,10 @@ mod tests {
     use serde_json::json;
 
     use super::*;
+    use crate::client::{
+        mock::MockTransport,
+        signature::{HmacSha1Signer, HmacSm3Signer},
+    };
 
     #[tokio::test]
     async fn roa_client_invalid_access_key_id_test() -> Result<()> {
@@ -428,4 +934,199 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn roa_client_mock_test() -> Result<()> {
+        let transport = Arc::new(MockTransport::new().access_key("id", "secret").on_path(
+            "POST",
+            "/clusters",
+            200,
+            r#"{"cluster_id":"c1"}"#,
+        ));
+
+        for signature_version in [SignatureVersion::V1, SignatureVersion::V3] {
+            let response = ROAClient::new("id", "secret", "https://cs.aliyuncs.com")
+                .transport(transport.clone())
+                .signature_version(signature_version)
+                .version("2015-12-15")
+                .action("CreateCluster")
+                .post("/clusters")
+                .query([("RegionId", "cn-hangzhou"), ("Name", "a b")])
+                .header([("content-type".to_string(), "application/json".to_string())])?
+                .body(json!({"name": "test"}).to_string())?
+                .text()
+                .await?;
+            assert_eq!(response, r#"{"cluster_id":"c1"}"#);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn roa_client_unsupported_signer_test() -> Result<()> {
+        let transport = Arc::new(MockTransport::new().access_key("id", "secret"));
+        let signers: [(SignatureVersion, Arc<dyn Signer>); 2] = [
+            (SignatureVersion::V1, Arc::new(HmacSm3Signer)),
+            (SignatureVersion::V3, Arc::new(HmacSha1Signer)),
+        ];
+        for (signature_version, signer) in signers {
+            let result = ROAClient::new("id", "secret", "https://cs.aliyuncs.com")
+                .transport(transport.clone())
+                .signature_version(signature_version)
+                .signer(signer)
+                .version("2015-12-15")
+                .action("DescribeClusters")
+                .get("/clusters")
+                .text()
+                .await;
+            assert!(matches!(result, Err(Error::InvalidRequest(_))));
+        }
+        assert!(transport.requests().is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn roa_client_v3_without_action_test() -> Result<()> {
+        let transport = Arc::new(MockTransport::new().access_key("id", "secret"));
+        let result = ROAClient::new("id", "secret", "https://cs.aliyuncs.com")
+            .transport(transport.clone())
+            .signature_version(SignatureVersion::V3)
+            .version("2015-12-15")
+            .get("/clusters")
+            .text()
+            .await;
+
+        assert!(matches!(result, Err(Error::InvalidRequest(_))));
+        assert!(transport.requests().is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn roa_client_typed_body_test() -> Result<()> {
+        #[derive(Serialize)]
+        #[serde(rename = "Cluster")]
+        struct Cluster {
+            name: String,
+            tags: Vec<String>,
+        }
+
+        let cluster = Cluster {
+            name: "test".to_string(),
+            tags: vec!["a b".to_string()],
+        };
+        let transport = MockTransport::new()
+            .access_key("id", "secret")
+            .on_path("POST", "/json", 200, "{}")
+            .on_path("POST", "/form", 200, "{}")
+            .on_path("POST", "/xml", 200, "{}");
+
+        let client = ROAClient::new("id", "secret", "https://cs.aliyuncs.com")
+            .transport(Arc::new(transport.clone()))
+            .version("2015-12-15");
+        client
+            .clone()
+            .post("/json")
+            .json_body(&cluster)?
+            .send()
+            .await?;
+        client
+            .clone()
+            .post("/form")
+            .form_body(&cluster)?
+            .send()
+            .await?;
+        client
+            .clone()
+            .post("/xml")
+            .xml_body(&cluster)?
+            .send()
+            .await?;
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 3);
+        let body = |index: usize| requests[index].body.clone().unwrap_or_default();
+        assert_eq!(requests[0].headers["content-type"], "application/json");
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body(0)).unwrap(),
+            json!({"name": "test", "tags": ["a b"]})
+        );
+        assert_eq!(
+            requests[1].headers["content-type"],
+            "application/x-www-form-urlencoded"
+        );
+        assert_eq!(body(1), "name=test&tags.1=a+b");
+        assert_eq!(requests[2].headers["content-type"], "application/xml");
+        assert_eq!(
+            body(2),
+            "<Cluster><name>test</name><tags>a b</tags></Cluster>"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn roa_client_binary_body_test() -> Result<()> {
+        let image = vec![0xff, 0xd8, 0xff, 0x00];
+        let text = "hello world";
+        let stream = move || {
+            futures::stream::iter(vec![
+                Ok::<_, std::io::Error>(Bytes::from("hello ")),
+                Ok(Bytes::from("world")),
+            ])
+        };
+        let transport = Arc::new(
+            MockTransport::new()
+                .access_key("id", "secret")
+                .on_path("POST", "/image", 200, "{}")
+                .on_path("POST", "/stream", 200, "{}"),
+        );
+
+        let client = ROAClient::new("id", "secret", "https://cs.aliyuncs.com")
+            .transport(transport.clone())
+            .version("2015-12-15");
+        client
+            .clone()
+            .post("/image")
+            .binary_body(image.clone())?
+            .send()
+            .await?;
+        client
+            .clone()
+            .post("/stream")
+            .stream_body(stream, text.len() as u64, Md5::digest(text).into())?
+            .send()
+            .await?;
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[0].headers["content-md5"],
+            base64::encode(Md5::digest(&image)).as_str()
+        );
+        assert_eq!(requests[0].body.as_deref(), Some(image.as_slice()));
+        // the streaming body is not held by the request.
+        assert_eq!(requests[1].headers["content-length"], "11");
+        assert_eq!(
+            requests[1].headers["content-md5"],
+            base64::encode(Md5::digest(text)).as_str()
+        );
+        assert_eq!(requests[1].body, None);
+
+        // streaming body can not be hashed by V3 signature.
+        let result = ROAClient::new("id", "secret", "https://cs.aliyuncs.com")
+            .transport(transport.clone())
+            .signature_version(SignatureVersion::V3)
+            .version("2015-12-15")
+            .action("UploadStream")
+            .post("/stream")
+            .stream_body(stream, text.len() as u64, Md5::digest(text).into())?
+            .send()
+            .await;
+        assert!(matches!(result, Err(Error::InvalidRequest(_))));
+        assert_eq!(transport.requests().len(), 2);
+
+        Ok(())
+    }
 }
@@ -1,14 +1,33 @@
-use std::{collections::HashMap, time::Duration};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
-use hmac::{Hmac, Mac};
-use reqwest::{header::HeaderMap, ClientBuilder, Response};
+use reqwest::{
+    header::{HeaderMap, HeaderName},
+    Client, Response,
+};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use sha1::Sha1;
 use time::{format_description::well_known::Iso8601, OffsetDateTime};
-use url::form_urlencoded::byte_serialize;
-use uuid::Uuid;
+use url::Url;
 
-use crate::client::error::{Error, Result};
+use crate::client::{
+    circuit_breaker::CircuitBreaker,
+    clock::{
+        is_clock_skew_error, Clock, ClockSkew, NonceGenerator, SystemClock, UuidNonceGenerator,
+    },
+    credential::{
+        Credential, CredentialProvider, DefaultCredentialChain, ProfileProvider,
+        StaticCredentialProvider,
+    },
+    error::{Error, Result},
+    middleware::{Middleware, RequestContext},
+    params::to_params,
+    rate_limit::RateLimiter,
+    retry::RetryPolicy,
+    signature::{
+        authorization_v3, canonical_query_string, check_signer, format_v3_date, hex_hash,
+        url_encode, SignatureVersion, Signer,
+    },
+    transport::{new_request, Body, HttpTransport, ReqwestTransport},
+};
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "PascalCase")]
@@ -28,13 +47,8 @@ pub struct RPCServiceError {
 /// Default const header.
 const AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
 const DEFAULT_HEADER: &[(&str, &str)] = &[("user-agent", AGENT), ("x-sdk-client", AGENT)];
-const DEFAULT_PARAM: &[(&str, &str)] = &[
-    ("Format", "JSON"),
-    ("SignatureMethod", "HMAC-SHA1"),
-    ("SignatureVersion", "1.0"),
-];
-
-type HamcSha1 = Hmac<Sha1>;
+const DEFAULT_PARAM: &[(&str, &str)] = &[("Format", "JSON"), ("SignatureVersion", "1.0")];
+const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
 
 /// Config for request.
 #[derive(Clone, Debug, Default)]
@@ -43,18 +57,43 @@ struct Request {
     method: String,
     query: Vec<(String, String)>,
     headers: HeaderMap,
+    body: Option<String>,
     version: String,
     timeout: Option<Duration>,
+    timestamp: Option<OffsetDateTime>,
+    anonymous: bool,
 }
 
 #[derive(Clone, Debug)]
 pub struct RPClient {
-    /// The access key id of aliyun developer account.
-    access_key_id: String,
-    /// The access key secret of aliyun developer account.
-    access_key_secret: String,
+    /// The provider of credentials to sign requests.
+    credential_provider: Arc<dyn CredentialProvider>,
     /// The api endpoint of aliyun api service (need start with http:// or https://).
     endpoint: String,
+    /// The version of signature.
+    signature_version: SignatureVersion,
+    /// The algorithm to sign requests.
+    signer: Option<Arc<dyn Signer>>,
+    /// The source of current time to sign requests.
+    clock: Arc<dyn Clock>,
+    /// The offset between local clock and server clock, shared by clones.
+    clock_skew: ClockSkew,
+    /// The source of nonces to sign requests.
+    nonce_generator: Arc<dyn NonceGenerator>,
+    /// The transport to send requests, shared by clones to reuse connections.
+    transport: Arc<dyn HttpTransport>,
+    /// The policy to retry failed requests.
+    retry_policy: RetryPolicy,
+    /// The limiter to throttle requests on client side.
+    rate_limiter: Option<RateLimiter>,
+    /// The circuit breaker to fail fast when the endpoint is unhealthy.
+    circuit_breaker: Option<CircuitBreaker>,
+    /// The middlewares to run around every attempt of request.
+    middlewares: Vec<Arc<dyn Middleware>>,
+    /// Whether to keep the system params of POST request in the query.
+    system_params_in_query: bool,
+    /// The default `RegionId` param of requests.
+    region_id: Option<String>,
     /// The config of http request.
     request: Request,
 }
@@ -65,11 +104,63 @@ impl RPClient {
         access_key_id: impl Into<String>,
         access_key_secret: impl Into<String>,
         endpoint: impl Into<String>,
+    ) -> Self {
+        Self::from_provider(
+            Arc::new(StaticCredentialProvider::new(
+                access_key_id,
+                access_key_secret,
+            )),
+            endpoint,
+        )
+    }
+
+    /// Create a api client with the default credential chain.
+    ///
+    /// See [`DefaultCredentialChain`] for where the credential is loaded from.
+    pub fn from_env(endpoint: impl Into<String>) -> Self {
+        Self::from_provider(Arc::new(DefaultCredentialChain::new()), endpoint)
+    }
+
+    /// Create a api client with the profile of aliyun CLI.
+    ///
+    /// See [`ProfileProvider`] for the supported modes of profile. The `region_id` of the
+    /// profile is sent as the `RegionId` param of requests which have no one.
+    pub fn from_profile(profile: impl Into<String>, endpoint: impl Into<String>) -> Self {
+        Self::from_profile_provider(ProfileProvider::new().profile(profile), endpoint)
+    }
+
+    /// Create a api client with the profile provider.
+    ///
+    /// Same as [`RPClient::from_profile`], with the config file or profile set on `provider`.
+    pub fn from_profile_provider(provider: ProfileProvider, endpoint: impl Into<String>) -> Self {
+        // the profile which fails to load is reported when getting the credential.
+        let region_id = provider.region_id().ok().flatten();
+        let mut client = Self::from_provider(Arc::new(provider), endpoint);
+        client.region_id = region_id;
+
+        client
+    }
+
+    /// Create a api client with the credential provider.
+    pub fn from_provider(
+        credential_provider: Arc<dyn CredentialProvider>,
+        endpoint: impl Into<String>,
     ) -> Self {
         RPClient {
-            access_key_id: access_key_id.into(),
-            access_key_secret: access_key_secret.into(),
+            credential_provider,
             endpoint: endpoint.into(),
+            signature_version: Default::default(),
+            signer: None,
+            clock: Arc::new(SystemClock),
+            clock_skew: Default::default(),
+            nonce_generator: Arc::new(UuidNonceGenerator),
+            transport: Arc::new(ReqwestTransport::default()),
+            retry_policy: RetryPolicy::disabled(),
+            rate_limiter: None,
+            circuit_breaker: None,
+            middlewares: Vec::new(),
+            system_params_in_query: false,
+            region_id: None,
             request: Default::default(),
         }
     }
@@ -98,7 +189,7 @@ impl RPClient {
         self.request("POST".to_string(), action.into())
     }
 
-    /// Set queries for request.
+    /// Set queries for request, the queries set before are replaced.
     ///
     /// Returns a `Self` for send request.
     pub fn query<I, T>(mut self, queries: I) -> Self
@@ -114,6 +205,39 @@ impl RPClient {
         self
     }
 
+    /// Set queries for request with a struct, which is flattened by [`to_params`].
+    /// The queries set before are replaced.
+    ///
+    /// Returns a `Self` for send request.
+    pub fn query_struct<T: Serialize + ?Sized>(mut self, queries: &T) -> Result<Self> {
+        self.request.query = to_params(queries)?;
+
+        Ok(self)
+    }
+
+    /// Add a query for request. If the query `key` is set, its value is replaced.
+    ///
+    /// Returns a `Self` for send request.
+    pub fn add_query(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let (key, value) = (key.into(), value.into());
+        match self.request.query.iter_mut().find(|(k, _)| *k == key) {
+            Some(query) => query.1 = value,
+            None => self.request.query.push((key, value)),
+        }
+
+        self
+    }
+
+    /// Add a query for request if the `value` is `Some`, see [`Self::add_query`].
+    ///
+    /// Returns a `Self` for send request.
+    pub fn query_opt(self, key: impl Into<String>, value: Option<impl Into<String>>) -> Self {
+        match value {
+            Some(value) => self.add_query(key, value),
+            None => self,
+        }
+    }
+
     /// Set version for request.
     ///
     /// Returns a `Self` for send request.
@@ -123,7 +247,7 @@ impl RPClient {
         self
     }
 
-    /// Set header for request.
+    /// Set header for request, the headers set before are replaced.
     ///
     /// Returns a `Self` for send request.
     pub fn header(mut self, headers: impl Into<HashMap<String, String>>) -> Result<Self> {
@@ -133,7 +257,137 @@ impl RPClient {
         Ok(self)
     }
 
-    /// Set a timeout for connect, read and write operations of a `Client`.
+    /// Add a header for request. If the header `key` is set, its value is replaced.
+    ///
+    /// Returns a `Self` for send request.
+    pub fn add_header(mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> Result<Self> {
+        let key = HeaderName::from_bytes(key.as_ref().as_bytes())
+            .map_err(|e| Error::InvalidRequest(format!("Cannot parse header: {e}")))?;
+        self.request.headers.insert(key, value.as_ref().parse()?);
+
+        Ok(self)
+    }
+
+    /// Set the version of signature.
+    ///
+    /// Default is [`SignatureVersion::V1`].
+    pub fn signature_version(mut self, signature_version: SignatureVersion) -> Self {
+        self.signature_version = signature_version;
+
+        self
+    }
+
+    /// Set the algorithm to sign requests.
+    ///
+    /// Default is `HMAC-SHA1` for V1 signature and `HMAC-SHA256` for V3 signature.
+    pub fn signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.signer = Some(signer);
+
+        self
+    }
+
+    /// Set the source of current time to sign requests.
+    ///
+    /// Default is [`SystemClock`].
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+
+        self
+    }
+
+    /// Set the source of nonces to sign requests.
+    ///
+    /// Default is [`UuidNonceGenerator`].
+    pub fn nonce_generator(mut self, nonce_generator: Arc<dyn NonceGenerator>) -> Self {
+        self.nonce_generator = nonce_generator;
+
+        self
+    }
+
+    /// Set the http client to send requests.
+    ///
+    /// The connection pool of the http client is shared by all requests and clones of
+    /// this client. Default is a `reqwest::Client` with default configs.
+    pub fn http_client(mut self, http_client: Client) -> Self {
+        self.transport = Arc::new(ReqwestTransport::new(http_client));
+
+        self
+    }
+
+    /// Set the transport to send signed requests.
+    ///
+    /// Default is [`ReqwestTransport`].
+    pub fn transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+
+        self
+    }
+
+    /// Set the policy to retry failed requests.
+    ///
+    /// Default is [`RetryPolicy::disabled`], which never retries.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+
+        self
+    }
+
+    /// Set the limiter to throttle requests on client side.
+    ///
+    /// Every attempt of request waits until the limits of the endpoint and the action allow it.
+    pub fn rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+
+        self
+    }
+
+    /// Set the circuit breaker to fail fast when the endpoint is unhealthy.
+    ///
+    /// Transport errors and 5xx responses are counted as failures of the endpoint.
+    pub fn circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(circuit_breaker);
+
+        self
+    }
+
+    /// Add a middleware to run around every attempt of request.
+    ///
+    /// See [`Middleware`] for the order of middlewares.
+    pub fn middleware(mut self, middleware: Arc<dyn Middleware>) -> Self {
+        self.middlewares.push(middleware);
+
+        self
+    }
+
+    /// Keep the system params of POST request, such as `Action` and `Signature`, in the
+    /// query, only the request params are sent in the form body.
+    ///
+    /// Default is `false`, all params of POST request are sent in the form body.
+    pub fn system_params_in_query(mut self, system_params_in_query: bool) -> Self {
+        self.system_params_in_query = system_params_in_query;
+
+        self
+    }
+
+    /// Set the default `RegionId` param, which is sent if the request has no `RegionId` query.
+    ///
+    /// Default is the `region_id` of profile for [`RPClient::from_profile`], or none.
+    pub fn region_id(mut self, region_id: impl Into<String>) -> Self {
+        self.region_id = Some(region_id.into());
+
+        self
+    }
+
+    /// Send request without credential and signature.
+    ///
+    /// Only for the APIs which allow anonymous access, such as STS `AssumeRoleWithOIDC`.
+    pub fn anonymous(mut self) -> Self {
+        self.request.anonymous = true;
+
+        self
+    }
+
+    /// Set a timeout for connect, read and write operations of a request.
     ///
     /// Default is no timeout.
     pub fn timeout(mut self, timeout: Duration) -> Self {
@@ -142,6 +396,39 @@ impl RPClient {
         self
     }
 
+    /// Set the timestamp to sign request, such as the time a pre-signed url is issued.
+    ///
+    /// Default is the current time of the clock.
+    pub fn timestamp(mut self, timestamp: OffsetDateTime) -> Self {
+        self.request.timestamp = Some(timestamp);
+
+        self
+    }
+
+    /// Sign the request without sending it.
+    /// Return the signed url, which can be sent by other processes or http clients.
+    ///
+    /// Only `GET` request with V1 signature is supported, as the url can not carry other
+    /// methods, and V3 signature is sent in headers.
+    pub async fn presign(mut self) -> Result<String> {
+        if self.request.method != "GET" {
+            return Err(Error::InvalidRequest(
+                "Pre-signed url only supports GET request".to_string(),
+            ));
+        }
+        if self.signature_version != SignatureVersion::V1 {
+            return Err(Error::InvalidRequest(
+                "Pre-signed url only supports V1 signature".to_string(),
+            ));
+        }
+
+        self.add_default_region();
+        let credential = self.credential().await?;
+        let params = self.sign_v1(self.current_signer().as_ref(), credential.as_ref())?;
+
+        Ok(url_with_query(&self.endpoint, &encode_params(&params)))
+    }
+
     /// Send a request to service.
     /// Try to deserialize the response body as JSON.
     pub async fn json<T: DeserializeOwned>(self) -> Result<T> {
@@ -154,26 +441,235 @@ impl RPClient {
         Ok(self.send().await?.text().await?)
     }
 
-    /// Send a request to service.
+    /// Send a request to service, retry the failed attempts following the retry policy.
     /// Return client Response.
-    pub async fn send(mut self) -> Result<Response> {
+    ///
+    /// If the failure is caused by clock skew, the offset of local clock is corrected with
+    /// the `Date` header of response, and the request is retried once.
+    pub async fn send(self) -> Result<Response> {
+        let offset = self.clock_skew.offset();
+        let result = self.send_with_retry().await;
+
+        // retry once with corrected time if the request failed by clock skew.
+        match result {
+            Err(e) if is_clock_skew_error(&e) && self.clock_skew.offset() != offset => {
+                self.send_with_retry().await
+            }
+            result => result,
+        }
+    }
+
+    /// Send a request, retry the failed attempts following the retry policy.
+    async fn send_with_retry(&self) -> Result<Response> {
+        self.retry_policy
+            .send(
+                &self.request.method,
+                || self.clone().send_once(),
+                |context, response| self.check_response(context, response),
+            )
+            .await
+    }
+
+    /// Get the current time, corrected with the detected clock skew.
+    fn now(&self) -> OffsetDateTime {
+        self.clock.now() + self.clock_skew.offset()
+    }
+
+    /// Build the context of request for middlewares.
+    fn context(&self) -> RequestContext {
+        RequestContext {
+            method: self.request.method.clone(),
+            endpoint: self.endpoint.clone(),
+            action: Some(self.request.action.clone()),
+            uri: String::new(),
+            version: self.request.version.clone(),
+            query: self.request.query.clone(),
+            headers: self.request.headers.clone(),
+        }
+    }
+
+    /// Apply the context of request changed by middlewares.
+    /// Add the default `RegionId` param if the request has no one.
+    fn add_default_region(&mut self) {
+        let Some(region_id) = self.region_id.as_ref() else {
+            return;
+        };
+        if !self.request.query.iter().any(|(k, _)| k == "RegionId") {
+            self.request
+                .query
+                .push(("RegionId".to_string(), region_id.clone()));
+        }
+    }
+
+    fn apply_context(&mut self, context: RequestContext) {
+        self.request.method = context.method;
+        self.endpoint = context.endpoint;
+        self.request.action = context.action.unwrap_or_default();
+        self.request.version = context.version;
+        self.request.query = context.query;
+        self.request.headers = context.headers;
+    }
+
+    /// Build, sign and send a single attempt of request.
+    /// Return the context of request and the response.
+    async fn send_once(mut self) -> Result<(RequestContext, Response)> {
+        self.add_default_region();
+
+        // run middlewares before signing.
+        let mut context = self.context();
+        for middleware in self.middlewares.iter() {
+            middleware.before_sign(&mut context).await?;
+        }
+        self.apply_context(context.clone());
+
+        // pass the error to middlewares if the attempt fails before the response arrives.
+        let middlewares = self.middlewares.clone();
+        match self.sign_and_send(&context).await {
+            Ok(response) => Ok((context, response)),
+            Err(e) => {
+                for middleware in middlewares.iter().rev() {
+                    middleware.after_response(&context, Err(&e)).await;
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Sign and send the request changed by middlewares.
+    async fn sign_and_send(mut self, context: &RequestContext) -> Result<Response> {
+        // wait for the rate limiter.
+        if let Some(rate_limiter) = self.rate_limiter.as_ref() {
+            rate_limiter
+                .acquire(&self.endpoint, &self.request.action)
+                .await;
+        }
+
         // add const header
         for (k, v) in DEFAULT_HEADER.iter() {
             self.request.headers.insert(*k, v.parse()?);
         }
 
+        // get credential, anonymous request is sent without credential.
+        let credential = self.credential().await?;
+
+        // sign request, get finnal request url.
+        let signer = self.current_signer();
+        let final_url = match self.signature_version {
+            SignatureVersion::V1 => {
+                let params = self.sign_v1(signer.as_ref(), credential.as_ref())?;
+                self.build_v1(params)?
+            }
+            SignatureVersion::V3 => self.sign_v3(signer.as_ref(), credential.as_ref())?,
+        };
+
+        // build http request.
+        let mut request = new_request(
+            &self.request.method,
+            &final_url,
+            &[],
+            self.request.headers,
+            self.request.body.map(|body| Body::Bytes(body.into())),
+        )?;
+
+        // run middlewares after signing.
+        for middleware in self.middlewares.iter() {
+            middleware.after_sign(context, &mut request).await?;
+        }
+
+        // check the circuit breaker.
+        if let Some(circuit_breaker) = self.circuit_breaker.as_ref() {
+            circuit_breaker.acquire(&self.endpoint)?;
+        }
+
+        // send request.
+        let result = self.transport.execute(request, self.request.timeout).await;
+        if let Some(circuit_breaker) = self.circuit_breaker.as_ref() {
+            let success = matches!(&result, Ok(response) if !response.status().is_server_error());
+            circuit_breaker.record(&self.endpoint, success);
+        }
+
+        result
+    }
+
+    /// Check HTTP StatusCode of response, and pass the result to middlewares.
+    async fn check_response(
+        &self,
+        context: RequestContext,
+        response: Response,
+    ) -> Result<Response> {
+        let date = response.headers().get("date").cloned();
+        let result = Self::error_for_status(response).await;
+        if let Err(e) = result.as_ref() {
+            self.clock_skew
+                .detect(self.clock.as_ref(), date.as_ref(), e);
+        }
+        for middleware in self.middlewares.iter().rev() {
+            middleware.after_response(&context, result.as_ref()).await;
+        }
+
+        result
+    }
+
+    /// Turn a response with failed HTTP status into an error.
+    async fn error_for_status(response: Response) -> Result<Response> {
+        if !response.status().is_success() {
+            let result = response.json::<RPCServiceError>().await?;
+            return Err(Error::InvalidResponse {
+                request_id: result.request_id,
+                error_code: result.code,
+                error_message: result.message,
+            });
+        }
+
+        Ok(response)
+    }
+
+    /// Get the credential to sign request, `None` for anonymous request.
+    async fn credential(&self) -> Result<Option<Credential>> {
+        if self.request.anonymous {
+            return Ok(None);
+        }
+
+        Ok(Some(self.credential_provider.get_credentials().await?))
+    }
+
+    /// Get the algorithm to sign request.
+    fn current_signer(&self) -> Arc<dyn Signer> {
+        self.signer
+            .clone()
+            .unwrap_or_else(|| self.signature_version.default_signer())
+    }
+
+    /// Sign request with V1 signature.
+    /// Return the params with signature.
+    fn sign_v1(
+        &self,
+        signer: &dyn Signer,
+        credential: Option<&Credential>,
+    ) -> Result<Vec<(String, String)>> {
+        check_signer(signer, SignatureVersion::V1)?;
+
         // build params.
-        let nonce = Uuid::new_v4().to_string();
-        let ts = OffsetDateTime::now_utc()
+        let nonce = self.nonce_generator.nonce();
+        let ts = self
+            .request
+            .timestamp
+            .unwrap_or_else(|| self.now())
             .format(&Iso8601::DEFAULT)
             .map_err(|e| Error::InvalidRequest(format!("Invalid ISO 8601 Date: {e}")))?;
 
         let mut params = Vec::from(DEFAULT_PARAM);
         params.push(("Action", &self.request.action));
-        params.push(("AccessKeyId", &self.access_key_id));
+        params.push(("SignatureMethod", signer.name()));
         params.push(("SignatureNonce", &nonce));
         params.push(("Timestamp", &ts));
         params.push(("Version", &self.request.version));
+        if let Some(credential) = credential {
+            params.push(("AccessKeyId", &credential.access_key_id));
+            if let Some(security_token) = credential.security_token.as_ref() {
+                params.push(("SecurityToken", security_token));
+            }
+        }
         params.extend(
             self.request
                 .query
@@ -181,92 +677,140 @@ impl RPClient {
                 .map(|(k, v)| (k.as_ref(), v.as_ref())),
         );
         params.sort_by_key(|item| item.0);
-
-        // encode params.
-        let params: Vec<String> = params
+        let mut params: Vec<(String, String)> = params
             .into_iter()
-            .map(|(k, v)| format!("{}={}", url_encode(k), url_encode(v)))
+            .map(|(k, v)| (k.to_string(), v.to_string()))
             .collect();
-        let sorted_query_string = params.join("&");
+
+        // sign params.
+        let Some(credential) = credential else {
+            return Ok(params);
+        };
         let string_to_sign = format!(
             "{}&{}&{}",
             self.request.method,
             url_encode("/"),
-            url_encode(&sorted_query_string)
+            url_encode(&encode_params(&params))
         );
+        let sign = signer.sign(
+            &format!("{}&", credential.access_key_secret),
+            string_to_sign.as_bytes(),
+        )?;
+        params.insert(0, ("Signature".to_string(), base64::encode(sign)));
 
-        // sign params, get finnal request url.
-        let sign = sign(&format!("{}&", self.access_key_secret), &string_to_sign)?;
-        let signature = url_encode(&sign);
-        let final_url = format!(
-            "{}?Signature={}&{}",
-            self.endpoint, signature, sorted_query_string
-        );
+        Ok(params)
+    }
 
-        // build http client.
-        let mut http_client_builder = ClientBuilder::new();
-        if let Some(timeout) = self.request.timeout {
-            http_client_builder = http_client_builder.timeout(timeout);
+    /// Build the request with the signed params of V1 signature.
+    /// Return the request url, the params of POST request are sent in the form body.
+    fn build_v1(&mut self, params: Vec<(String, String)>) -> Result<String> {
+        if self.request.method != "POST" {
+            return Ok(url_with_query(&self.endpoint, &encode_params(&params)));
         }
-        let http_client = http_client_builder.build()?.request(
+
+        let (query, body): (Vec<_>, Vec<_>) = params.into_iter().partition(|(k, _)| {
+            self.system_params_in_query && !self.request.query.iter().any(|(q, _)| q == k)
+        });
+        self.request
+            .headers
+            .insert("content-type", FORM_CONTENT_TYPE.parse()?);
+        self.request.body = Some(encode_params(&body));
+
+        Ok(url_with_query(&self.endpoint, &encode_params(&query)))
+    }
+
+    /// Sign request with V3 signature, add signed headers.
+    /// Return the request url with params.
+    fn sign_v3(&mut self, signer: &dyn Signer, credential: Option<&Credential>) -> Result<String> {
+        check_signer(signer, SignatureVersion::V3)?;
+
+        // add host header.
+        let endpoint = Url::parse(&self.endpoint)
+            .map_err(|e| Error::InvalidRequest(format!("Invalid endpoint: {e}")))?;
+        let host = endpoint
+            .host_str()
+            .ok_or_else(|| Error::InvalidRequest(format!("Invalid endpoint: {endpoint}")))?;
+        self.request.headers.insert("host", host.parse()?);
+
+        // send params of POST request in the form body.
+        let query = if self.request.method == "POST" && !self.request.query.is_empty() {
             self.request
-                .method
-                .parse()
-                .map_err(|e| Error::InvalidRequest(format!("Invalid HTTP method: {}", e)))?,
-            &final_url,
-        );
+                .headers
+                .insert("content-type", FORM_CONTENT_TYPE.parse()?);
+            self.request.body = Some(canonical_query_string(&self.request.query));
+            Vec::new()
+        } else {
+            self.request.query.clone()
+        };
+        let content_hash = hex_hash(
+            signer,
+            self.request.body.as_deref().unwrap_or_default().as_bytes(),
+        )?;
 
-        // send request.
-        let response = http_client.headers(self.request.headers).send().await?;
+        // add signed headers.
+        let nonce = self.nonce_generator.nonce();
+        let ts = format_v3_date(self.request.timestamp.unwrap_or_else(|| self.now()))?;
+        let headers = &mut self.request.headers;
+        headers.insert("x-acs-action", self.request.action.parse()?);
+        headers.insert("x-acs-version", self.request.version.parse()?);
+        headers.insert("x-acs-date", ts.parse()?);
+        headers.insert("x-acs-signature-nonce", nonce.parse()?);
+        headers.insert(signer.content_hash_header(), content_hash.parse()?);
 
-        // check HTTP StatusCode.
-        if !response.status().is_success() {
-            let result = response.json::<RPCServiceError>().await?;
-            return Err(Error::InvalidResponse {
-                request_id: result.request_id,
-                error_code: result.code,
-                error_message: result.message,
-            });
+        // compute `Authorization` header.
+        if let Some(credential) = credential {
+            if let Some(security_token) = credential.security_token.as_ref() {
+                headers.insert("x-acs-security-token", security_token.parse()?);
+            }
+            let authorization = authorization_v3(
+                signer,
+                credential,
+                &self.request.method,
+                endpoint.path(),
+                &query,
+                headers,
+            )?;
+            headers.insert("Authorization", authorization.parse()?);
         }
 
-        // return response.
-        Ok(response)
+        Ok(url_with_query(
+            &self.endpoint,
+            &canonical_query_string(&query),
+        ))
     }
 }
 
-fn sign(key: &str, body: &str) -> Result<String> {
-    let mut mac = HamcSha1::new_from_slice(key.as_bytes())
-        .map_err(|e| Error::InvalidRequest(format!("Invalid HMAC-SHA1 secret key: {}", e)))?;
-    mac.update(body.as_bytes());
-    let result = mac.finalize();
-    let code = result.into_bytes();
-
-    Ok(base64::encode(code))
+/// Encode the params as query string or form body.
+fn encode_params(params: &[(String, String)]) -> String {
+    params
+        .iter()
+        .map(|(k, v)| format!("{}={}", url_encode(k), url_encode(v)))
+        .collect::<Vec<_>>()
+        .join("&")
 }
 
-/// URL encode following [RFC3986](https://www.rfc-editor.org/rfc/rfc3986)
-fn url_encode(s: &str) -> String {
-    let s: String = byte_serialize(s.as_bytes()).collect();
-    s.replace('+', "%20")
-        .replace('*', "%2A")
-        .replace("%7E", "~")
+/// Append the query string to url.
+fn url_with_query(endpoint: &str, query_string: &str) -> String {
+    if query_string.is_empty() {
+        endpoint.to_string()
+    } else {
+        format!("{}?{}", endpoint, query_string)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::env;
 
-    use super::*;
-
-    #[test]
-    fn url_encode_test() -> Result<()> {
-        assert_eq!(
-            url_encode("begin_+_*_~_-_._\"_ end"),
-            "begin_%2B_%2A_~_-_._%22_%20end"
-        );
+    use mockito::Matcher;
+    use time::macros::datetime;
 
-        Ok(())
-    }
+    use super::*;
+    use crate::client::{
+        credential::Env,
+        mock::MockTransport,
+        signature::{HmacSha1Signer, HmacSm3Signer},
+    };
 
     #[tokio::test]
     async fn rpc_client_invalid_access_key_id_test() -> Result<()> {
@@ -310,4 +854,474 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn rpc_client_v3_signature_test() -> Result<()> {
+        let transport = MockTransport::new().access_key("id", "secret").on_action(
+            "DescribeInstances",
+            200,
+            "{}",
+        );
+
+        let aliyun_openapi_client = RPClient::from_provider(
+            Arc::new(StaticCredentialProvider::with_security_token(
+                "id", "secret", "token",
+            )),
+            "https://ecs.aliyuncs.com/",
+        )
+        .transport(Arc::new(transport.clone()));
+        let response = aliyun_openapi_client
+            .signature_version(SignatureVersion::V3)
+            .version("2014-05-26")
+            .get("DescribeInstances")
+            .query([("RegionId", "cn-hangzhou")])
+            .text()
+            .await?;
+
+        assert_eq!(response, "{}");
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        let headers = &requests[0].headers;
+        assert_eq!(headers["x-acs-action"], "DescribeInstances");
+        assert_eq!(headers["x-acs-version"], "2014-05-26");
+        assert_eq!(headers["x-acs-security-token"], "token");
+        assert!(headers["authorization"].to_str().unwrap().starts_with(
+            "ACS3-HMAC-SHA256 Credential=id,SignedHeaders=host;x-acs-action;\
+             x-acs-content-sha256;x-acs-date;x-acs-security-token;\
+             x-acs-signature-nonce;x-acs-version,Signature="
+        ));
+        assert!(requests[0]
+            .url
+            .query_pairs()
+            .any(|(k, v)| k == "RegionId" && v == "cn-hangzhou"));
+
+        Ok(())
+    }
+
+    // mockito is used as the custom http client of the default transport is under test.
+    #[tokio::test]
+    async fn rpc_client_with_http_client_test() -> Result<()> {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/")
+            .match_query(Matcher::UrlEncoded(
+                "Action".into(),
+                "DescribeRegions".into(),
+            ))
+            .match_header("x-custom-header", "custom")
+            .with_body("{}")
+            .expect(2)
+            .create_async()
+            .await;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-custom-header", "custom".parse()?);
+        let http_client = Client::builder().default_headers(headers).build()?;
+        let aliyun_openapi_client =
+            RPClient::new("id", "secret", format!("{}/", server.url())).http_client(http_client);
+
+        // clones share the same http client.
+        for _ in 0..2 {
+            let response = aliyun_openapi_client
+                .clone()
+                .version("2014-05-26")
+                .get("DescribeRegions")
+                .text()
+                .await?;
+            assert_eq!(response, "{}");
+        }
+        mock.assert_async().await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rpc_client_retry_test() -> Result<()> {
+        let transport = MockTransport::new().access_key("id", "secret").on_action(
+            "DescribeRegions",
+            400,
+            r#"{"RequestId":"request-id","Code":"Throttling.User","Message":"Request was denied due to user flow control."}"#,
+        );
+
+        let aliyun_openapi_client = RPClient::new("id", "secret", "https://ecs.aliyuncs.com/")
+            .transport(Arc::new(transport.clone()))
+            .retry_policy(
+                RetryPolicy::new()
+                    .max_attempts(3)
+                    .backoff(Duration::from_millis(1), Duration::from_millis(10)),
+            );
+        let result = aliyun_openapi_client
+            .version("2014-05-26")
+            .get("DescribeRegions")
+            .text()
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::InvalidResponse { error_code, .. }) if error_code == "Throttling.User"
+        ));
+        assert_eq!(transport.requests().len(), 3);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rpc_client_retry_post_test() -> Result<()> {
+        let transport = MockTransport::new().access_key("id", "secret").on_action(
+            "RunInstances",
+            500,
+            r#"{"RequestId":"request-id","Code":"InternalError","Message":"The request processing has failed due to some unknown error."}"#,
+        );
+        let client = RPClient::new("id", "secret", "https://ecs.aliyuncs.com/")
+            .transport(Arc::new(transport.clone()))
+            .version("2014-05-26");
+        let retry_policy = RetryPolicy::new()
+            .max_attempts(3)
+            .backoff(Duration::from_millis(1), Duration::from_millis(10));
+
+        // the POST request may have been processed, it is not retried by default.
+        let result = client
+            .clone()
+            .retry_policy(retry_policy.clone())
+            .post("RunInstances")
+            .text()
+            .await;
+        assert!(matches!(
+            result,
+            Err(Error::InvalidResponse { error_code, .. }) if error_code == "InternalError"
+        ));
+        assert_eq!(transport.requests().len(), 1);
+
+        client
+            .retry_policy(retry_policy.retry_sent_requests(true))
+            .post("RunInstances")
+            .text()
+            .await
+            .unwrap_err();
+        assert_eq!(transport.requests().len(), 4);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rpc_client_circuit_breaker_test() -> Result<()> {
+        let transport = MockTransport::new().access_key("id", "secret").on_action(
+            "DescribeRegions",
+            503,
+            r#"{"RequestId":"request-id","Code":"ServiceUnavailable","Message":"The request has failed due to a temporary failure of the server."}"#,
+        );
+
+        let aliyun_openapi_client = RPClient::new("id", "secret", "https://ecs.aliyuncs.com/")
+            .transport(Arc::new(transport.clone()))
+            .circuit_breaker(CircuitBreaker::new().failure_threshold(2));
+        for _ in 0..2 {
+            let result = aliyun_openapi_client
+                .clone()
+                .version("2014-05-26")
+                .get("DescribeRegions")
+                .text()
+                .await;
+            assert!(matches!(result, Err(Error::InvalidResponse { .. })));
+        }
+
+        // fail fast without sending request.
+        let result = aliyun_openapi_client
+            .version("2014-05-26")
+            .get("DescribeRegions")
+            .text()
+            .await;
+        assert!(matches!(result, Err(Error::CircuitOpen(_))));
+        assert_eq!(transport.requests().len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rpc_client_presign_test() -> Result<()> {
+        let url = RPClient::new("id", "secret", "https://ecs.aliyuncs.com/")
+            .version("2014-05-26")
+            .get("DescribeRegions")
+            .query(vec![("RegionId", "cn-hangzhou")])
+            .timestamp(datetime!(2023-10-26 10:22:32 UTC))
+            .presign()
+            .await?;
+        assert!(url.starts_with("https://ecs.aliyuncs.com/?Signature="));
+        assert!(url.contains("Timestamp=2023-10-26T10%3A22%3A32"));
+
+        // the pre-signed url can be sent by other http clients.
+        let transport = MockTransport::new().access_key("id", "secret").on_action(
+            "DescribeRegions",
+            200,
+            r#"{"Regions":{}}"#,
+        );
+        let request = http::Request::builder()
+            .uri(&url)
+            .body(Body::default())
+            .unwrap();
+        let response = transport.send(request).await?;
+        assert_eq!(response.status(), 200);
+
+        // V3 signature can not be pre-signed.
+        let result = RPClient::new("id", "secret", "https://ecs.aliyuncs.com/")
+            .signature_version(SignatureVersion::V3)
+            .version("2014-05-26")
+            .get("DescribeRegions")
+            .presign()
+            .await;
+        assert!(matches!(result, Err(Error::InvalidRequest(_))));
+
+        // POST request can not be pre-signed.
+        let result = RPClient::new("id", "secret", "https://ecs.aliyuncs.com/")
+            .version("2014-05-26")
+            .post("DescribeRegions")
+            .presign()
+            .await;
+        assert!(matches!(result, Err(Error::InvalidRequest(_))));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rpc_client_unsupported_signer_test() -> Result<()> {
+        let transport = Arc::new(MockTransport::new().access_key("id", "secret"));
+        let signers: [(SignatureVersion, Arc<dyn Signer>); 2] = [
+            (SignatureVersion::V1, Arc::new(HmacSm3Signer)),
+            (SignatureVersion::V3, Arc::new(HmacSha1Signer)),
+        ];
+        for (signature_version, signer) in signers {
+            let result = RPClient::new("id", "secret", "https://ecs.aliyuncs.com/")
+                .transport(transport.clone())
+                .signature_version(signature_version)
+                .signer(signer)
+                .version("2014-05-26")
+                .get("DescribeRegions")
+                .text()
+                .await;
+            assert!(matches!(result, Err(Error::InvalidRequest(_))));
+        }
+        assert!(transport.requests().is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rpc_client_from_env_test() -> Result<()> {
+        // `from_env` is the default credential chain with the env vars of the process.
+        let env = Env::from([
+            ("ALIBABA_CLOUD_ACCESS_KEY_ID", "env-id"),
+            ("ALIBABA_CLOUD_ACCESS_KEY_SECRET", "env-secret"),
+            ("ALIBABA_CLOUD_ECS_METADATA_DISABLED", "true"),
+        ]);
+        let response = RPClient::from_provider(
+            Arc::new(DefaultCredentialChain::with_env(env)),
+            "https://ecs.aliyuncs.com/",
+        )
+        .transport(Arc::new(
+            MockTransport::new()
+                .access_key("env-id", "env-secret")
+                .on_action("DescribeRegions", 200, r#"{"Regions":{}}"#),
+        ))
+        .version("2014-05-26")
+        .get("DescribeRegions")
+        .text()
+        .await?;
+        assert_eq!(response, r#"{"Regions":{}}"#);
+
+        let result = RPClient::from_provider(
+            Arc::new(DefaultCredentialChain::with_env(Env::from([(
+                "ALIBABA_CLOUD_ECS_METADATA_DISABLED",
+                "true",
+            )]))),
+            "https://ecs.aliyuncs.com/",
+        )
+        .transport(Arc::new(MockTransport::new()))
+        .version("2014-05-26")
+        .get("DescribeRegions")
+        .text()
+        .await;
+        assert!(matches!(result, Err(Error::InvalidCredential(_))));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rpc_client_from_profile_test() -> Result<()> {
+        let config_file =
+            env::temp_dir().join(format!("aliyun-config-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &config_file,
+            serde_json::json!({
+                "current": "default",
+                "profiles": [{
+                    "name": "default",
+                    "mode": "AK",
+                    "access_key_id": "id",
+                    "access_key_secret": "secret",
+                    "region_id": "cn-hangzhou"
+                }]
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let transport = Arc::new(MockTransport::new().access_key("id", "secret").on_action(
+            "DescribeInstances",
+            200,
+            r#"{"Instances":{}}"#,
+        ));
+        let client = RPClient::from_profile_provider(
+            ProfileProvider::new().config_file(&config_file),
+            "https://ecs.aliyuncs.com/",
+        )
+        .transport(transport.clone())
+        .version("2014-05-26");
+
+        // the `region_id` of profile is the default `RegionId`.
+        client.clone().get("DescribeInstances").text().await?;
+        // the `RegionId` of request is not replaced.
+        client
+            .get("DescribeInstances")
+            .query([("RegionId", "cn-beijing")])
+            .text()
+            .await?;
+        std::fs::remove_file(&config_file).unwrap();
+
+        let region_ids: Vec<_> = transport
+            .requests()
+            .iter()
+            .map(|request| {
+                request
+                    .url
+                    .query_pairs()
+                    .filter(|(k, _)| k == "RegionId")
+                    .map(|(_, v)| v.into_owned())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        assert_eq!(region_ids, [vec!["cn-hangzhou"], vec!["cn-beijing"]]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rpc_client_post_form_test() -> Result<()> {
+        let transport = MockTransport::new().access_key("id", "secret").on_action(
+            "RunInstances",
+            200,
+            r#"{"InstanceIdSets":{}}"#,
+        );
+        let client = RPClient::new("id", "secret", "https://ecs.aliyuncs.com/")
+            .transport(Arc::new(transport.clone()))
+            .version("2014-05-26");
+        let user_data = "a".repeat(16 * 1024);
+        for (signature_version, system_params_in_query) in [
+            (SignatureVersion::V1, false),
+            (SignatureVersion::V1, true),
+            (SignatureVersion::V3, false),
+        ] {
+            client
+                .clone()
+                .signature_version(signature_version)
+                .system_params_in_query(system_params_in_query)
+                .post("RunInstances")
+                .query(vec![("UserData", user_data.as_str())])
+                .text()
+                .await?;
+        }
+
+        let requests: Vec<_> = transport
+            .requests()
+            .into_iter()
+            .map(|request| {
+                let body = request.body.unwrap_or_default();
+                (request.url, String::from_utf8_lossy(&body).into_owned())
+            })
+            .collect();
+        // all params are sent in the form body.
+        assert_eq!(requests[0].0.query(), None);
+        assert!(requests[0].1.contains("Action=RunInstances"));
+        assert!(requests[0].1.contains("Signature="));
+        assert!(requests[0].1.contains(&format!("UserData={user_data}")));
+        // system params are kept in the query.
+        assert!(requests[1].0.query_pairs().any(|(k, _)| k == "Signature"));
+        assert!(!requests[1].0.query_pairs().any(|(k, _)| k == "UserData"));
+        assert_eq!(requests[1].1, format!("UserData={user_data}"));
+        // V3 signature sends request params in the form body.
+        assert_eq!(requests[2].0.query(), None);
+        assert_eq!(requests[2].1, format!("UserData={user_data}"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rpc_client_query_struct_test() -> Result<()> {
+        #[derive(Serialize)]
+        #[serde(rename_all = "PascalCase")]
+        struct DescribeInstances {
+            region_id: String,
+            instance_ids: Vec<String>,
+        }
+
+        let transport = MockTransport::new().access_key("id", "secret").on_action(
+            "DescribeInstances",
+            200,
+            r#"{"Instances":{}}"#,
+        );
+        RPClient::new("id", "secret", "https://ecs.aliyuncs.com/")
+            .transport(Arc::new(transport.clone()))
+            .version("2014-05-26")
+            .get("DescribeInstances")
+            .query_struct(&DescribeInstances {
+                region_id: "cn-hangzhou".to_string(),
+                instance_ids: vec!["i-1".to_string(), "i-2".to_string()],
+            })?
+            .text()
+            .await?;
+
+        let requests = transport.requests();
+        let query: Vec<(String, String)> = requests[0].url.query_pairs().into_owned().collect();
+        for param in [
+            ("RegionId", "cn-hangzhou"),
+            ("InstanceIds.1", "i-1"),
+            ("InstanceIds.2", "i-2"),
+        ] {
+            assert!(query.contains(&(param.0.to_string(), param.1.to_string())));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rpc_client_add_query_test() -> Result<()> {
+        let transport = MockTransport::new().access_key("id", "secret").on_action(
+            "DescribeInstances",
+            200,
+            r#"{"Instances":{}}"#,
+        );
+        RPClient::new("id", "secret", "https://ecs.aliyuncs.com/")
+            .transport(Arc::new(transport.clone()))
+            .version("2014-05-26")
+            .get("DescribeInstances")
+            .query([("RegionId", "cn-beijing"), ("PageSize", "10")])
+            .add_query("RegionId", "cn-hangzhou")
+            .query_opt("NextToken", None::<String>)
+            .query_opt("InstanceName", Some("test"))
+            .text()
+            .await?;
+
+        let requests = transport.requests();
+        let query: Vec<(String, String)> = requests[0].url.query_pairs().into_owned().collect();
+        let value = |key: &str| {
+            query
+                .iter()
+                .filter(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(value("RegionId"), vec!["cn-hangzhou"]);
+        assert_eq!(value("PageSize"), vec!["10"]);
+        assert_eq!(value("InstanceName"), vec!["test"]);
+        assert!(value("NextToken").is_empty());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,221 @@
+use std::{fmt::Debug, time::Duration};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::{header::HeaderMap, Method};
+use reqwest::{Client, Response};
+use url::Url;
+
+pub use crate::client::body::{Body, BodyStream};
+use crate::client::error::{Error, Result};
+
+/// The signed request sent by transports.
+pub type HttpRequest = http::Request<Body>;
+
+/// The response received by transports.
+pub type HttpResponse = http::Response<Bytes>;
+
+/// The transport to send signed requests.
+///
+/// The default transport is [`ReqwestTransport`]. A custom transport can send requests
+/// with another HTTP stack, or mock and record requests in tests.
+#[async_trait]
+pub trait HttpTransport: Debug + Send + Sync {
+    /// Send a signed request, return the status, headers and body of response.
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse>;
+
+    /// Send a signed request, fail if the response is not received in `timeout`.
+    ///
+    /// The default implementation buffers the response of [`send`](Self::send), so the body
+    /// can not be streamed and `Response::url` is not the request url. The `timeout` is
+    /// enforced with the tokio timer, which requires a tokio runtime with time enabled.
+    async fn execute(&self, request: HttpRequest, timeout: Option<Duration>) -> Result<Response> {
+        let response = match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, self.send(request))
+                .await
+                .map_err(|_| Error::Timeout(timeout))??,
+            None => self.send(request).await?,
+        };
+
+        Ok(response.into())
+    }
+}
+
+/// The transport to send requests with a `reqwest::Client`.
+#[derive(Clone, Debug, Default)]
+pub struct ReqwestTransport {
+    /// The http client to send requests, connections are pooled across requests.
+    http_client: Client,
+}
+
+impl ReqwestTransport {
+    /// Create a transport with the http client.
+    pub fn new(http_client: Client) -> Self {
+        ReqwestTransport { http_client }
+    }
+}
+
+#[async_trait]
+impl HttpTransport for ReqwestTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse> {
+        let response = self.http_client.execute(reqwest_request(request)?).await?;
+        let mut builder = http::Response::builder()
+            .status(response.status())
+            .version(response.version());
+        if let Some(headers) = builder.headers_mut() {
+            *headers = response.headers().clone();
+        }
+
+        builder
+            .body(response.bytes().await?)
+            .map_err(|e| Error::InvalidRequest(format!("Invalid response: {e}")))
+    }
+
+    /// Send the request with reqwest and return its response unbuffered, so the body can be
+    /// streamed and `Response::url` is the request url. The `timeout` is enforced by reqwest.
+    async fn execute(&self, request: HttpRequest, timeout: Option<Duration>) -> Result<Response> {
+        let mut request = reqwest_request(request)?;
+        *request.timeout_mut() = timeout;
+
+        Ok(self.http_client.execute(request).await?)
+    }
+}
+
+/// Convert the signed request to a reqwest request.
+fn reqwest_request(request: HttpRequest) -> Result<reqwest::Request> {
+    let (parts, body) = request.into_parts();
+    let mut request = reqwest::Request::new(parts.method, request_url(&parts.uri)?);
+    *request.headers_mut() = parts.headers;
+    *request.body_mut() = match body {
+        Body::Bytes(bytes) if bytes.is_empty() => None,
+        Body::Bytes(bytes) => Some(bytes.into()),
+        Body::Stream(stream) => Some(reqwest::Body::wrap_stream(stream())),
+    };
+
+    Ok(request)
+}
+
+/// Create a request with the `method`, `url`, `headers` and `body`.
+///
+/// The `query` is appended to the url with form encoding.
+pub(crate) fn new_request(
+    method: &str,
+    url: &str,
+    query: &[(String, String)],
+    headers: HeaderMap,
+    body: Option<Body>,
+) -> Result<HttpRequest> {
+    let method: Method = method
+        .parse()
+        .map_err(|e| Error::InvalidRequest(format!("Invalid HTTP method: {}", e)))?;
+    let mut url =
+        Url::parse(url).map_err(|e| Error::InvalidRequest(format!("Invalid url: {e}")))?;
+    if !query.is_empty() {
+        url.query_pairs_mut().extend_pairs(query);
+    }
+
+    let mut request = http::Request::new(body.unwrap_or_default());
+    *request.method_mut() = method;
+    *request.uri_mut() = url
+        .as_str()
+        .parse()
+        .map_err(|e| Error::InvalidRequest(format!("Invalid url: {e}")))?;
+    *request.headers_mut() = headers;
+
+    Ok(request)
+}
+
+/// Parse the uri of request as url.
+pub(crate) fn request_url(uri: &http::Uri) -> Result<Url> {
+    Url::parse(&uri.to_string()).map_err(|e| Error::InvalidRequest(format!("Invalid url: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::client::{mock::MockTransport, rpc::RPClient};
+
+    #[tokio::test]
+    async fn transport_test() -> Result<()> {
+        let transport = MockTransport::new().access_key("id", "secret").on_action(
+            "DescribeRegions",
+            200,
+            r#"{"RequestId":"request-id"}"#,
+        );
+        let response = RPClient::new("id", "secret", "https://ecs.aliyuncs.com/")
+            .transport(Arc::new(transport.clone()))
+            .version("2014-05-26")
+            .get("DescribeRegions")
+            .text()
+            .await?;
+
+        assert_eq!(response, r#"{"RequestId":"request-id"}"#);
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].url.host_str(), Some("ecs.aliyuncs.com"));
+        assert!(requests[0]
+            .url
+            .query_pairs()
+            .any(|(k, v)| k == "Action" && v == "DescribeRegions"));
+
+        Ok(())
+    }
+
+    // mockito is used as the real HTTP behavior of `ReqwestTransport` is under test.
+    #[tokio::test]
+    async fn reqwest_transport_execute_test() -> Result<()> {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/regions")
+            .with_body("streamed body")
+            .create_async()
+            .await;
+        let url = format!("{}/regions", server.url());
+        let request = new_request("GET", &url, &[], HeaderMap::new(), None)?;
+
+        let response = ReqwestTransport::default()
+            .execute(request, Some(Duration::from_secs(10)))
+            .await?;
+
+        assert_eq!(response.url().as_str(), url);
+        let chunks: Vec<_> = response.bytes_stream().collect().await;
+        let body: Vec<u8> = chunks.into_iter().flat_map(|c| c.unwrap()).collect();
+        assert_eq!(body, b"streamed body");
+        mock.assert_async().await;
+
+        Ok(())
+    }
+
+    /// The transport which never responds.
+    #[derive(Debug)]
+    struct PendingTransport;
+
+    #[async_trait]
+    impl HttpTransport for PendingTransport {
+        async fn send(&self, _request: HttpRequest) -> Result<HttpResponse> {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn transport_execute_timeout_test() -> Result<()> {
+        let request = new_request(
+            "GET",
+            "https://ecs.aliyuncs.com/",
+            &[],
+            HeaderMap::new(),
+            None,
+        )?;
+        let timeout = Duration::from_millis(10);
+
+        let result = PendingTransport.execute(request, Some(timeout)).await;
+
+        assert!(matches!(result, Err(Error::Timeout(t)) if t == timeout));
+
+        Ok(())
+    }
+}
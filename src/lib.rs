@@ -89,7 +89,6 @@
 //!
 //! use aliyun_openapi_core_rust_sdk::client::roa::ROAClient;
 //! use serde::{Deserialize, Serialize};
-//! use serde_json::json;
 //!
 //! #[derive(Serialize, Deserialize, Debug)]
 //! #[serde(rename_all = "PascalCase")]
@@ -127,8 +126,7 @@
 //!     let response = aliyun_openapi_client
 //!         .version("2018-04-08")
 //!         .post("/api/translate/web/general")
-//!         .header([("Content-Type".to_string(), "application/json".to_string())])?
-//!         .body(json!(params).to_string())?
+//!         .json_body(&params)?
 //!         .json::<Translate>()
 //!         .await?;
 //!
@@ -140,11 +138,11 @@
 //!
 //! ## Examples
 //!
-//! Export AK info to env, then run `cargo run --example <NAME>`:
+//! Export AK info to env (or configure a profile of aliyun CLI), then run `cargo run --example <NAME>`:
 //!
 //! ```sh
-//! export ACCESS_KEY_ID=<access_key_id>
-//! export ACCESS_KEY_SECRET=<access_key_secret>
+//! export ALIBABA_CLOUD_ACCESS_KEY_ID=<access_key_id>
+//! export ALIBABA_CLOUD_ACCESS_KEY_SECRET=<access_key_secret>
 //!
 //! # ecs example
 //! cargo run --example ecs
@@ -163,7 +161,10 @@
 //! ```
 #![allow(deprecated)]
 
+// the deprecated clients are kept unchanged, rustc 1.89+ warns on their elided lifetimes.
+#[allow(unknown_lints, mismatched_lifetime_syntaxes)]
 mod roa;
+#[allow(unknown_lints, mismatched_lifetime_syntaxes)]
 mod rpc;
 
 #[deprecated(